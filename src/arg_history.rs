@@ -0,0 +1,54 @@
+use crate::utils::config::APP_ID;
+use crate::utils::error::{AppError, AppResult};
+use crate::utils::provider::EntryId;
+use crate::utils::script_args::ScriptArg;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const CONFIG_NAME: &str = "arg_history";
+
+/// Last values entered in the argument form, per entry and parameter name
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ArgHistory {
+    pub values: HashMap<EntryId, HashMap<String, String>>,
+}
+
+impl ArgHistory {
+    pub fn load() -> Self {
+        confy::load(APP_ID, Some(CONFIG_NAME)).unwrap_or_default()
+    }
+
+    pub fn save(&self) -> AppResult<()> {
+        confy::store(APP_ID, Some(CONFIG_NAME), self).map_err(|source| AppError::SaveConfig {
+            what: "argument history",
+            source,
+        })
+    }
+
+    /// Starting values for the form: the last ones used where still valid, else the defaults
+    pub fn initial_values(&self, id: &EntryId, args: &[ScriptArg]) -> Vec<String> {
+        let remembered = self.values.get(id);
+        args.iter()
+            .map(|arg| {
+                remembered
+                    .and_then(|values| values.get(&arg.name))
+                    .filter(|value| arg.validate(value).is_ok())
+                    .cloned()
+                    .unwrap_or_else(|| arg.default.clone())
+            })
+            .collect()
+    }
+
+    pub fn remember(
+        &mut self,
+        id: &EntryId,
+        args: &[ScriptArg],
+        values: &[String],
+    ) -> AppResult<()> {
+        let entry = self.values.entry(id.clone()).or_default();
+        for (arg, value) in args.iter().zip(values) {
+            entry.insert(arg.name.clone(), value.clone());
+        }
+        self.save()
+    }
+}
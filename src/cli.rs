@@ -0,0 +1,374 @@
+use crate::preferences::AppPreferences;
+use crate::usage_stats::{UsageStats, today};
+use crate::utils::build_tree::{Entry, RootFolder, build_tree, find_entry};
+use crate::utils::config::APP_ID;
+use crate::utils::launch::{LaunchMode, spawn_attached, spawn_detached, spawn_in_terminal};
+use crate::utils::provider::{
+    EntryId, ProviderSection, SCRIPT_PROVIDER, discover, find_discovered, provider,
+};
+use crate::utils::search::search;
+use clap::{Parser, Subcommand};
+use std::error::Error;
+use std::path::Path;
+use std::process::ExitCode;
+
+/// How many near misses to suggest when `run` finds no script by that name
+const MAX_SUGGESTIONS: usize = 5;
+
+type CliResult<T> = Result<T, Box<dyn Error>>;
+
+/// Launch scripts from a GUI, or from the command line with a subcommand
+#[derive(Parser)]
+#[command(name = APP_ID, version)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<CliCommand>,
+}
+
+#[derive(Subcommand)]
+pub enum CliCommand {
+    /// Print the scripts found in every enabled root
+    List {
+        /// Print the tree as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Launch a script by name or path and record the launch
+    Run {
+        /// File name, header name, path relative to its root, or full path
+        target: String,
+        /// Run in this terminal and wait for it instead of opening a new terminal
+        #[arg(long)]
+        background: bool,
+        /// Arguments passed on to the script
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+    /// Print the frecency score and launch count of each script, highest score first
+    Stats {
+        /// Print the counts as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Read or change preferences
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Print one preference, or all of them as TOML
+    Get {
+        /// Dotted key such as `num_cols` or `roots.0.label`
+        key: Option<String>,
+    },
+    /// Change one preference; the value is read as TOML, falling back to a plain string
+    Set { key: String, value: String },
+}
+
+pub fn run(command: CliCommand) -> CliResult<ExitCode> {
+    match command {
+        CliCommand::List { json } => list(json)?,
+        CliCommand::Run {
+            target,
+            background,
+            args,
+        } => return run_script(&target, background, &args),
+        CliCommand::Stats { json } => stats(json)?,
+        CliCommand::Config { action } => match action {
+            ConfigAction::Get { key } => config_get(key.as_deref())?,
+            ConfigAction::Set { key, value } => config_set(&key, &value)?,
+        },
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// The tree of every enabled root, and what the providers find by themselves
+fn scan(preferences: &AppPreferences) -> (Vec<RootFolder>, Vec<ProviderSection>) {
+    let roots = build_tree(preferences.enabled_roots(), &preferences.scan);
+    (roots, discover(preferences))
+}
+
+fn list(json: bool) -> CliResult<()> {
+    let (roots, discovered) = scan(&AppPreferences::load());
+    for root in &roots {
+        for warning in &root.warnings {
+            eprintln!("warning: {}: {warning}", root.label);
+        }
+    }
+    if json {
+        println!("{}", serde_json::to_string_pretty(&roots)?);
+        return Ok(());
+    }
+    for root in &roots {
+        println!("{} ({})", root.label, root.path.display());
+        for entry in &root.entries {
+            println!("  {}\t{}", entry.label(), entry.path.display());
+        }
+        for folder in &root.folders {
+            for entry in &folder.flat_entries {
+                println!(
+                    "  {}/{}\t{}",
+                    folder.name,
+                    entry.display_name,
+                    entry.path.display()
+                );
+            }
+        }
+    }
+    for section in &discovered {
+        println!("{}", section.label);
+        for entry in &section.entries {
+            println!("  {}\t{}", entry.label(), entry.path.display());
+        }
+    }
+    Ok(())
+}
+
+fn run_script(target: &str, background: bool, args: &[String]) -> CliResult<ExitCode> {
+    let preferences = AppPreferences::load();
+    let (roots, discovered) = scan(&preferences);
+    let entry = resolve_target(&roots, &discovered, target)?;
+    let mode = if background {
+        LaunchMode::Background
+    } else {
+        LaunchMode::declared_by(&entry.meta)
+    };
+
+    let command = entry.command(args);
+    match mode {
+        LaunchMode::Background => {
+            let mut child = spawn_attached(&command)?;
+            record_launch(&entry.id);
+            let status = child.wait()?;
+            let code = status.code().and_then(|code| u8::try_from(code).ok());
+            return Ok(code.map_or(ExitCode::FAILURE, ExitCode::from));
+        }
+        LaunchMode::Detached => spawn_detached(&command)?,
+        LaunchMode::Terminal => {
+            let (profile, keep_open) = preferences.terminal_for(Some(&entry.meta));
+            spawn_in_terminal(&command, &profile, keep_open)?;
+        }
+    }
+    record_launch(&entry.id);
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Count a launch that got started. The stats are a nicety, so failing to save them only earns a
+/// warning.
+fn record_launch(id: &EntryId) {
+    // Saving the launch saves the migrated stats as well, so only that save's result matters
+    let (mut usage_stats, _migrated) = UsageStats::load();
+    if let Err(error) = usage_stats.increment(id) {
+        eprintln!("warning: {error}");
+    }
+}
+
+/// Find the entry meant by `target`: an executable file, or an exact (then case-insensitive) match
+/// on file name, header name or path relative to its root, or on the name or label of an entry a
+/// provider found by itself, such as an application's desktop file ID.
+fn resolve_target(
+    roots: &[RootFolder],
+    discovered: &[ProviderSection],
+    target: &str,
+) -> CliResult<Entry> {
+    let as_path = Path::new(target);
+    if as_path.is_file() {
+        let path = as_path
+            .canonicalize()
+            .unwrap_or_else(|_| as_path.to_path_buf());
+        return provider(SCRIPT_PROVIDER)
+            .and_then(|scripts| scripts.read_file(&path))
+            .and_then(|entries| entries.into_iter().next())
+            .ok_or_else(|| format!("{} is not executable", path.display()).into());
+    }
+
+    let candidates: Vec<(Vec<String>, &EntryId)> = roots
+        .iter()
+        .flat_map(|root| {
+            root.entries
+                .iter()
+                .map(|entry| {
+                    let names = vec![entry.name.clone(), entry.label().to_owned()];
+                    (names, &entry.id)
+                })
+                .chain(root.folders.iter().flat_map(|folder| {
+                    folder.flat_entries.iter().map(|entry| {
+                        let key = Path::new(entry.id.key());
+                        let relative = key
+                            .strip_prefix(&root.path)
+                            .unwrap_or(key)
+                            .to_string_lossy()
+                            .into_owned();
+                        let mut names = vec![
+                            relative,
+                            format!("{}/{}", folder.name, entry.display_name),
+                            entry.name.clone(),
+                        ];
+                        names.extend(entry.meta.name.clone());
+                        (names, &entry.id)
+                    })
+                }))
+        })
+        .chain(
+            discovered
+                .iter()
+                .flat_map(|section| &section.entries)
+                .map(|entry| {
+                    let names = vec![entry.name.clone(), entry.label().to_owned()];
+                    (names, &entry.id)
+                }),
+        )
+        .collect();
+
+    let exact: Vec<&EntryId> = candidates
+        .iter()
+        .filter(|(names, _)| names.iter().any(|name| name == target))
+        .map(|(_, id)| *id)
+        .collect();
+    let matches = if exact.is_empty() {
+        candidates
+            .iter()
+            .filter(|(names, _)| names.iter().any(|name| name.eq_ignore_ascii_case(target)))
+            .map(|(_, id)| *id)
+            .collect()
+    } else {
+        exact
+    };
+
+    match matches.as_slice() {
+        [id] => Ok(find_entry(roots, id)
+            .or_else(|| find_discovered(discovered, id))
+            .cloned()
+            .expect("candidates come from the tree or the discovered entries")),
+        [] => {
+            let suggestions: Vec<String> = search(roots, discovered, target, &|_| 0.0)
+                .iter()
+                .take(MAX_SUGGESTIONS)
+                .map(|hit| format!("  {}", hit.id))
+                .collect();
+            if suggestions.is_empty() {
+                Err(format!("no script named {target}").into())
+            } else {
+                Err(format!(
+                    "no script named {target}; did you mean:\n{}",
+                    suggestions.join("\n")
+                )
+                .into())
+            }
+        }
+        several => {
+            let ids: Vec<String> = several.iter().map(|id| format!("  {id}")).collect();
+            Err(format!("{target} is ambiguous:\n{}", ids.join("\n")).into())
+        }
+    }
+}
+
+fn stats(json: bool) -> CliResult<()> {
+    let half_life_days = AppPreferences::load().usage_half_life_days;
+    let (usage_stats, migrated) = UsageStats::load();
+    if let Err(error) = migrated {
+        eprintln!("warning: {error}");
+    }
+    let today = today();
+    let mut rows: Vec<(String, u64, f64)> = usage_stats
+        .history
+        .iter()
+        .map(|(id, history)| {
+            let frecency = history.frecency(today, half_life_days);
+            (id.to_string(), history.launches(), frecency)
+        })
+        .collect();
+    rows.sort_by(|a, b| b.2.total_cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
+    if json {
+        let rows: Vec<serde_json::Value> = rows
+            .iter()
+            .map(|(id, launches, frecency)| {
+                serde_json::json!({ "id": id, "launches": launches, "frecency": frecency })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+        return Ok(());
+    }
+    for (id, launches, frecency) in rows {
+        println!("{frecency:.2}\t{launches}\t{id}");
+    }
+    Ok(())
+}
+
+fn config_get(key: Option<&str>) -> CliResult<()> {
+    let preferences = AppPreferences::load();
+    let Some(key) = key else {
+        print!("{}", toml::to_string_pretty(&preferences)?);
+        return Ok(());
+    };
+    let value = toml::Value::try_from(&preferences)?;
+    match lookup(&value, key) {
+        Some(toml::Value::String(text)) => println!("{text}"),
+        Some(value) => println!("{value}"),
+        None => return Err(format!("unknown preference {key}").into()),
+    }
+    Ok(())
+}
+
+fn config_set(key: &str, raw: &str) -> CliResult<()> {
+    let preferences = AppPreferences::load();
+    let mut value = toml::Value::try_from(&preferences)?;
+    let (parent_key, last) = match key.rsplit_once('.') {
+        Some((parent_key, last)) => (Some(parent_key), last),
+        None => (None, key),
+    };
+    let parent = match parent_key {
+        Some(parent_key) => lookup_mut(&mut value, parent_key),
+        None => Some(&mut value),
+    };
+    let new_value = parse_toml_value(raw);
+    match parent {
+        // Unset optional preferences are missing from the table, so allow adding keys
+        Some(toml::Value::Table(table)) => {
+            table.insert(last.to_owned(), new_value);
+        }
+        Some(toml::Value::Array(items)) => {
+            let slot = last
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| items.get_mut(index))
+                .ok_or_else(|| format!("unknown preference {key}"))?;
+            *slot = new_value;
+        }
+        _ => return Err(format!("unknown preference {key}").into()),
+    }
+    // Round-trip through the struct so a value of the wrong type is rejected
+    let preferences: AppPreferences = value
+        .try_into()
+        .map_err(|err| format!("invalid value for {key}: {err}"))?;
+    preferences.save()?;
+    Ok(())
+}
+
+fn parse_toml_value(raw: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {raw}"))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_owned()))
+}
+
+fn lookup<'a>(value: &'a toml::Value, key: &str) -> Option<&'a toml::Value> {
+    key.split('.')
+        .try_fold(value, |value, segment| match value {
+            toml::Value::Table(table) => table.get(segment),
+            toml::Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+            _ => None,
+        })
+}
+
+fn lookup_mut<'a>(value: &'a mut toml::Value, key: &str) -> Option<&'a mut toml::Value> {
+    key.split('.')
+        .try_fold(value, |value, segment| match value {
+            toml::Value::Table(table) => table.get_mut(segment),
+            toml::Value::Array(items) => items.get_mut(segment.parse::<usize>().ok()?),
+            _ => None,
+        })
+}
@@ -1,10 +1,29 @@
 use crate::gui::QuickLaunchApp;
+use crate::gui::navigation::{NavScope, consume_nav_keys};
+use crate::gui::output_panel::output_panel;
 use eframe::Frame;
 use egui::Context;
 
 impl eframe::App for QuickLaunchApp {
     fn update(&mut self, ctx: &Context, _frame: &mut Frame) {
+        self.poll_scan(ctx);
+        self.poll_discovered(ctx);
+        self.poll_watcher(ctx);
+        // Claim navigation keys before the search field gets a chance to act on them
+        let scope = if self.dialog_open() {
+            NavScope::Dialog
+        } else {
+            NavScope::Grid {
+                claim_horizontal: self.search_query.is_empty(),
+            }
+        };
+        let nav_keys = consume_nav_keys(ctx, scope);
+        let nav_keys = self.confirm_modal(ctx, nav_keys);
+        let nav_keys = self.arg_form_modal(ctx, nav_keys);
+        let nav_keys = self.settings_window(ctx, nav_keys);
         self.top_panel(ctx);
-        self.action_panel(ctx);
+        output_panel(ctx, &mut self.background_runs);
+        self.action_panel(ctx, nav_keys);
+        self.toasts.show(ctx);
     }
 }
@@ -0,0 +1,142 @@
+use crate::gui::navigation::NavKey;
+use crate::utils::launch::{LaunchMode, pick_file_async};
+use crate::utils::provider::EntryId;
+use crate::utils::script_args::{ArgKind, ScriptArg, build_argv};
+use crate::utils::task::Task;
+use std::path::PathBuf;
+
+/// What the user did with the argument form this frame
+pub enum FormOutcome {
+    Open,
+    /// Validated argv, ready to pass to the script
+    Submit(Vec<String>),
+    Cancel,
+}
+
+/// Modal form collecting the parameters a script declares in its header
+pub struct ArgForm {
+    pub id: EntryId,
+    pub title: String,
+    pub args: Vec<ScriptArg>,
+    pub values: Vec<String>,
+    pub mode: LaunchMode,
+    /// Keep the launcher open after this launch whatever the preference says
+    pub stay_open: bool,
+    /// Pending file dialog and the index of the path parameter it fills
+    pick_task: Option<(usize, Task<Option<PathBuf>>)>,
+}
+
+impl ArgForm {
+    pub fn new(
+        id: EntryId,
+        title: String,
+        args: Vec<ScriptArg>,
+        values: Vec<String>,
+        mode: LaunchMode,
+        stay_open: bool,
+    ) -> Self {
+        ArgForm {
+            id,
+            title,
+            args,
+            values,
+            mode,
+            stay_open,
+            pick_task: None,
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        self.args
+            .iter()
+            .zip(&self.values)
+            .all(|(arg, value)| arg.validate(value).is_ok())
+    }
+
+    /// Draw the form; Enter in `nav_keys` submits it and Escape cancels it.
+    pub fn show(&mut self, ctx: &egui::Context, nav_keys: &[NavKey]) -> FormOutcome {
+        if let Some((index, task)) = &mut self.pick_task
+            && let Some(picked) = task.try_take()
+        {
+            if let Some(picked) = picked {
+                self.values[*index] = picked.to_string_lossy().into_owned();
+            }
+            self.pick_task = None;
+        }
+
+        let mut submit = nav_keys.contains(&NavKey::Activate);
+        let mut cancel = nav_keys.contains(&NavKey::Close);
+        let mut pick_for = None;
+        egui::Modal::new(egui::Id::new("arg_form")).show(ctx, |ui| {
+            ui.heading(&self.title);
+            egui::Grid::new("arg_form_fields")
+                .num_columns(2)
+                .spacing(egui::vec2(12.0, 8.0))
+                .show(ui, |ui| {
+                    for (index, (arg, value)) in self.args.iter().zip(&mut self.values).enumerate()
+                    {
+                        ui.label(&arg.name);
+                        ui.vertical(|ui| {
+                            match &arg.kind {
+                                ArgKind::Bool => {
+                                    let mut checked = value == "true";
+                                    if ui.checkbox(&mut checked, "").changed() {
+                                        *value = checked.to_string();
+                                    }
+                                }
+                                ArgKind::Choice(choices) => {
+                                    egui::ComboBox::from_id_salt(("arg_choice", index))
+                                        .selected_text(value.as_str())
+                                        .show_ui(ui, |ui| {
+                                            for choice in choices {
+                                                ui.selectable_value(value, choice.clone(), choice);
+                                            }
+                                        });
+                                }
+                                ArgKind::Path => {
+                                    ui.horizontal(|ui| {
+                                        ui.text_edit_singleline(value);
+                                        if ui.button("Browse").clicked() {
+                                            pick_for = Some(index);
+                                        }
+                                    });
+                                }
+                                ArgKind::String | ArgKind::Int | ArgKind::Words => {
+                                    ui.text_edit_singleline(value);
+                                }
+                            }
+                            if let Some(help) = &arg.help {
+                                ui.weak(help);
+                            }
+                            if let Err(problem) = arg.validate(value) {
+                                ui.colored_label(ui.visuals().error_fg_color, problem);
+                            }
+                        });
+                        ui.end_row();
+                    }
+                });
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                submit |= ui
+                    .add_enabled(self.is_valid(), egui::Button::new("Run"))
+                    .clicked();
+                cancel |= ui.button("Cancel").clicked();
+            });
+        });
+
+        if let Some(index) = pick_for
+            && self.pick_task.is_none()
+        {
+            self.pick_task = Some((index, pick_file_async()));
+        }
+
+        if cancel {
+            FormOutcome::Cancel
+        } else if submit && self.is_valid() {
+            FormOutcome::Submit(build_argv(&self.args, &self.values))
+        } else {
+            FormOutcome::Open
+        }
+    }
+}
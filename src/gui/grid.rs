@@ -0,0 +1,299 @@
+use crate::gui::highlight::highlighted_label;
+use crate::utils::metadata::ScriptMeta;
+use crate::utils::provider::EntryId;
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+/// One labelled grid of launcher buttons
+pub struct GridSection<'a> {
+    pub id: egui::Id,
+    pub header: Option<SectionHeader<'a>>,
+    pub cells: Vec<GridCell<'a>>,
+    /// Buttons can be dragged onto each other to change their order
+    pub reorderable: bool,
+}
+
+/// Title drawn above a grid
+pub enum SectionHeader<'a> {
+    /// A configured script root, with its optional accent colour
+    Root(&'a str, Option<[u8; 3]>),
+    Folder(&'a str),
+    /// A folder in the nested layout, `depth` levels below its root; clicking it toggles `open`
+    TreeFolder {
+        name: &'a str,
+        path: PathBuf,
+        depth: usize,
+        open: bool,
+    },
+}
+
+/// Something the user did to a launcher button
+pub enum GridEvent {
+    /// Clicked, with the modifiers held at the time
+    Launch {
+        id: EntryId,
+        modifiers: egui::Modifiers,
+    },
+    /// Picked from the button's context menu
+    Action(EntryId, CellAction),
+    /// The button at index `from` of a reorderable grid was dropped onto the one at `to`
+    Move { from: usize, to: usize },
+}
+
+/// Entries of a launcher button's context menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellAction {
+    RunInTerminal,
+    RunInBackground,
+    RunWithArguments,
+    OpenInEditor,
+    RevealInFileManager,
+    CopyPath,
+    CopyCommandLine,
+    TogglePin,
+    Hide,
+    ResetUsage,
+}
+
+/// Indentation of a nested folder header per level
+const TREE_INDENT: f32 = 16.0;
+
+/// Drag payload carrying the index of the button being moved
+struct DraggedCell(usize);
+
+/// A single launcher button
+pub struct GridCell<'a> {
+    pub label: egui::WidgetText,
+    pub hover_text: Cow<'a, str>,
+    pub id: &'a EntryId,
+    pub pinned: bool,
+    /// The entry is not in any enabled root; the button is greyed out and can't be launched
+    pub missing: bool,
+    /// Highlight strength from 0 to 1, for a script that was just launched
+    pub flash: f32,
+}
+
+impl<'a> GridCell<'a> {
+    /// Build a button for an entry read from the file at `path`, highlighting the chars of
+    /// `label` at `indices`
+    pub fn new(
+        ui: &egui::Ui,
+        label: &str,
+        indices: &[usize],
+        id: &'a EntryId,
+        path: &'a Path,
+        meta: &'a ScriptMeta,
+    ) -> Self {
+        let hover_text = match &meta.description {
+            Some(description) => Cow::Borrowed(description.as_str()),
+            None => path.to_string_lossy(),
+        };
+        GridCell {
+            label: highlighted_label(ui, meta.icon, label, indices).into(),
+            hover_text,
+            id,
+            pinned: false,
+            missing: false,
+            flash: 0.0,
+        }
+    }
+
+    /// Button for a pinned entry that was deleted, moved, or whose root was disabled
+    pub fn missing(id: &'a EntryId) -> Self {
+        let key = Path::new(id.key());
+        let name = key.file_name().unwrap_or(key.as_os_str()).to_string_lossy();
+        GridCell {
+            label: egui::RichText::new(format!("{name} (missing)"))
+                .weak()
+                .into(),
+            hover_text: Cow::Owned(format!("Not found in any enabled root: {id}")),
+            id,
+            pinned: true,
+            missing: true,
+            flash: 0.0,
+        }
+    }
+
+    pub fn pinned(mut self, pinned: bool) -> Self {
+        self.pinned = pinned;
+        self
+    }
+}
+
+/// Lay out a section's buttons in rows of `num_cols`, returning what the user did to them.
+///
+/// `selected` is the index of the keyboard-selected button within this grid, if it lives here.
+/// Right-clicking a button opens its context menu.
+pub fn entry_grid(
+    ui: &mut egui::Ui,
+    section: GridSection,
+    button_size: egui::Vec2,
+    num_cols: usize,
+    selected: Option<usize>,
+    scroll_to_selected: bool,
+) -> Option<GridEvent> {
+    let spacing = ui.style().spacing.item_spacing.x;
+    let mut event = None;
+    egui::Grid::new(section.id)
+        .spacing(egui::vec2(spacing, spacing))
+        .show(ui, |ui| {
+            let mut cells = section.cells.into_iter().enumerate().peekable();
+            while cells.peek().is_some() {
+                for (index, cell) in cells.by_ref().take(num_cols) {
+                    let is_selected = selected == Some(index);
+                    let button = egui::Button::new(cell.label).selected(is_selected);
+                    let response = if section.reorderable {
+                        let drag_id = section.id.with(index);
+                        ui.dnd_drag_source(drag_id, DraggedCell(index), |ui| {
+                            ui.add_sized(button_size, button)
+                        })
+                        .inner
+                    } else {
+                        ui.add_sized(button_size, button)
+                    };
+                    let response = response.on_hover_text(cell.hover_text.as_ref());
+                    if cell.flash > 0.0 {
+                        let color = ui.visuals().selection.bg_fill;
+                        ui.painter().rect_filled(
+                            response.rect,
+                            4.0,
+                            color.gamma_multiply(0.5 * cell.flash),
+                        );
+                    }
+                    if is_selected && scroll_to_selected {
+                        response.scroll_to_me(None);
+                    }
+                    if section.reorderable {
+                        if response.dnd_hover_payload::<DraggedCell>().is_some() {
+                            let stroke = ui.visuals().selection.stroke;
+                            ui.painter().rect_stroke(
+                                response.rect,
+                                4.0,
+                                stroke,
+                                egui::StrokeKind::Outside,
+                            );
+                        }
+                        if let Some(dragged) = response.dnd_release_payload::<DraggedCell>()
+                            && dragged.0 != index
+                        {
+                            event = Some(GridEvent::Move {
+                                from: dragged.0,
+                                to: index,
+                            });
+                        }
+                    }
+                    if response.clicked() && !cell.missing {
+                        event = Some(GridEvent::Launch {
+                            id: cell.id.clone(),
+                            modifiers: ui.input(|input| input.modifiers),
+                        });
+                    }
+                    response.context_menu(|ui| {
+                        if let Some(action) = cell_menu(ui, cell.pinned, cell.missing) {
+                            event = Some(GridEvent::Action(cell.id.clone(), action));
+                            ui.close_menu();
+                        }
+                    });
+                }
+                ui.end_row();
+            }
+        });
+    event
+}
+
+/// Context menu entries for a button; a missing script can only be unpinned or have its path
+/// copied
+fn cell_menu(ui: &mut egui::Ui, pinned: bool, missing: bool) -> Option<CellAction> {
+    let mut chosen = None;
+    let mut item = |ui: &mut egui::Ui, text: &str, action: CellAction| {
+        if ui.button(text).clicked() {
+            chosen = Some(action);
+        }
+    };
+    let pin_text = if pinned { "Unpin" } else { "Pin to favorites" };
+    if missing {
+        item(ui, pin_text, CellAction::TogglePin);
+        item(ui, "Copy path", CellAction::CopyPath);
+        return chosen;
+    }
+    item(ui, "Run in terminal", CellAction::RunInTerminal);
+    item(ui, "Run in background", CellAction::RunInBackground);
+    item(ui, "Run with arguments…", CellAction::RunWithArguments);
+    ui.separator();
+    item(ui, "Open in editor", CellAction::OpenInEditor);
+    item(
+        ui,
+        "Reveal in file manager",
+        CellAction::RevealInFileManager,
+    );
+    item(ui, "Copy path", CellAction::CopyPath);
+    item(ui, "Copy command line", CellAction::CopyCommandLine);
+    ui.separator();
+    item(ui, pin_text, CellAction::TogglePin);
+    item(ui, "Hide", CellAction::Hide);
+    item(ui, "Reset usage count", CellAction::ResetUsage);
+    chosen
+}
+
+/// Draw a section's title, returning whether a collapsible header was clicked
+pub fn section_header(ui: &mut egui::Ui, header: &SectionHeader) -> bool {
+    match header {
+        SectionHeader::Root(label, color) => root_header(ui, label, *color),
+        SectionHeader::Folder(name) => {
+            ui.separator();
+            folder_header(ui, name);
+        }
+        SectionHeader::TreeFolder {
+            name, depth, open, ..
+        } => return tree_header(ui, name, *depth, *open),
+    }
+    false
+}
+
+/// Draw a folder name with an expand/collapse arrow, indented by its depth
+fn tree_header(ui: &mut egui::Ui, folder_name: &str, depth: usize, open: bool) -> bool {
+    ui.horizontal(|ui| {
+        ui.add_space(depth as f32 * TREE_INDENT);
+        let icon_size = egui::Vec2::splat(ui.spacing().icon_width);
+        let (_, icon) = ui.allocate_exact_size(icon_size, egui::Sense::click());
+        egui::collapsing_header::paint_default_icon(ui, if open { 1.0 } else { 0.0 }, &icon);
+        let label =
+            egui::Label::new(egui::RichText::new(folder_name).strong()).sense(egui::Sense::click());
+        let label = ui.add(label);
+        icon.clicked() || label.clicked()
+    })
+    .inner
+}
+
+/// Draw a script root's label as a heading with a coloured accent bar
+fn root_header(ui: &mut egui::Ui, label: &str, color: Option<[u8; 3]>) {
+    let accent = color
+        .map(|[r, g, b]| egui::Color32::from_rgb(r, g, b))
+        .unwrap_or(ui.visuals().selection.bg_fill);
+    ui.add_space(4.0);
+    ui.horizontal(|ui| {
+        let (rect, _) = ui.allocate_exact_size(egui::vec2(4.0, 20.0), egui::Sense::hover());
+        ui.painter().rect_filled(rect, 2.0, accent);
+        ui.heading(label);
+    });
+}
+
+/// Draw a folder name on a rounded badge above its grid
+fn folder_header(ui: &mut egui::Ui, folder_name: &str) {
+    let padding = egui::vec2(8.0, 4.0);
+    let galley = ui.painter().layout_no_wrap(
+        folder_name.to_owned(),
+        egui::FontId::default(),
+        egui::Color32::WHITE,
+    );
+    let (rect, _) = ui.allocate_exact_size(galley.size() + padding * 2.0, egui::Sense::hover());
+    ui.painter()
+        .rect_filled(rect, 4.0, egui::Color32::from_gray(40));
+    ui.painter().text(
+        rect.center(),
+        egui::Align2::CENTER_CENTER,
+        folder_name,
+        egui::FontId::default(),
+        egui::Color32::WHITE,
+    );
+}
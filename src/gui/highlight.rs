@@ -0,0 +1,53 @@
+use crate::utils::config::ICON_FONT_NAME;
+use egui::FontFamily;
+use egui::text::{LayoutJob, TextFormat};
+
+/// Lay out `text` with the chars at `indices` drawn in the selection colour, preceded by an
+/// optional Nerd Font `icon`.
+pub fn highlighted_label(
+    ui: &egui::Ui,
+    icon: Option<char>,
+    text: &str,
+    indices: &[usize],
+) -> LayoutJob {
+    let font_id = egui::TextStyle::Button.resolve(ui.style());
+    let normal = TextFormat {
+        font_id: font_id.clone(),
+        color: ui.visuals().text_color(),
+        ..Default::default()
+    };
+    let highlight = TextFormat {
+        font_id: font_id.clone(),
+        color: ui.visuals().selection.stroke.color,
+        underline: ui.visuals().selection.stroke,
+        ..Default::default()
+    };
+
+    let mut job = LayoutJob::default();
+    if let Some(icon) = icon {
+        let icon_format = TextFormat {
+            font_id: egui::FontId::new(font_id.size, FontFamily::Name(ICON_FONT_NAME.into())),
+            color: ui.visuals().text_color(),
+            ..Default::default()
+        };
+        job.append(&icon.to_string(), 0.0, icon_format);
+        job.append(" ", 0.0, normal.clone());
+    }
+    let mut run = String::new();
+    let mut run_highlighted = false;
+    for (idx, ch) in text.chars().enumerate() {
+        let is_highlighted = indices.contains(&idx);
+        if is_highlighted != run_highlighted && !run.is_empty() {
+            let format = if run_highlighted { &highlight } else { &normal };
+            job.append(&run, 0.0, format.clone());
+            run.clear();
+        }
+        run_highlighted = is_highlighted;
+        run.push(ch);
+    }
+    if !run.is_empty() {
+        let format = if run_highlighted { highlight } else { normal };
+        job.append(&run, 0.0, format);
+    }
+    job
+}
@@ -4,6 +4,8 @@ use egui::RichText;
 
 const FOLDER_ICON_CHAR: char = '\u{ea83}';
 const FOLDER_MOVE_ICON_CHAR: char = '\u{e5fc}';
+const CLOSE_ICON_CHAR: char = '\u{f00d}';
+const SETTINGS_ICON_CHAR: char = '\u{f013}';
 
 pub fn icon_button(ui: &mut egui::Ui, codepoint: char) -> egui::Response {
     let icon = RichText::new(codepoint.to_string()).font(egui::FontId::new(
@@ -20,3 +22,11 @@ pub fn folder_button(ui: &mut egui::Ui) -> egui::Response {
 pub fn folder_open_dialog(ui: &mut egui::Ui) -> egui::Response {
     icon_button(ui, FOLDER_MOVE_ICON_CHAR)
 }
+
+pub fn close_button(ui: &mut egui::Ui) -> egui::Response {
+    icon_button(ui, CLOSE_ICON_CHAR)
+}
+
+pub fn settings_button(ui: &mut egui::Ui) -> egui::Response {
+    icon_button(ui, SETTINGS_ICON_CHAR)
+}
@@ -1,202 +1,1074 @@
 mod app;
+mod arg_form;
 mod assets;
+mod grid;
+mod highlight;
 mod icon_button;
+mod navigation;
+mod output_panel;
+mod recent;
+mod settings;
+mod toasts;
 
+use crate::arg_history::ArgHistory;
+use crate::gui::arg_form::{ArgForm, FormOutcome};
 use crate::gui::assets::setup_fonts;
-use crate::gui::icon_button::{folder_button, folder_open_dialog};
-use crate::preferences::AppPreferences;
+use crate::gui::grid::{
+    CellAction, GridCell, GridEvent, GridSection, SectionHeader, entry_grid, section_header,
+};
+use crate::gui::icon_button::{close_button, folder_button, folder_open_dialog, settings_button};
+use crate::gui::navigation::{NavKey, step};
+use crate::gui::recent::RecentLaunches;
+use crate::gui::settings::SettingsWindow;
+use crate::gui::toasts::Toasts;
+use crate::preferences::{AppPreferences, FolderLayout, ScriptRoot, SortMode};
+use crate::scan_cache::ScanCache;
 use crate::usage_stats::UsageStats;
-use crate::utils::build_tree::{RootFolder, build_tree};
-use crate::utils::launch::{open_native_file_viewer, pick_folder_async, spawn_script_in_terminal};
-use crate::utils::task::Task;
+use crate::utils::background::{BackgroundRun, spawn_background};
+use crate::utils::build_tree::{
+    Entry, Folder, RootFolder, ScanWarning, TreeUpdate, find_entry, rebuild_changed, scan_root,
+};
+use crate::utils::launch::{
+    LaunchMode, open_in_editor, open_native_file_viewer, pick_folder_async, spawn_detached,
+    spawn_in_terminal,
+};
+use crate::utils::provider::{EntryId, ProviderSection, discover, find_discovered, provider};
+use crate::utils::script_args::{ArgKind, ScriptArg};
+use crate::utils::search::search;
+use crate::utils::task::{StreamTask, Task};
+use crate::utils::watch::DirWatcher;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How often to check on a tree rebuild running in the background
+const REBUILD_POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// Scan warnings listed in the hover text of a root's warning line
+const MAX_LISTED_WARNINGS: usize = 20;
+
+/// A launch held back until the user confirms it
+#[derive(Clone)]
+struct PendingLaunch {
+    id: EntryId,
+    /// Arguments already collected from the parameter form
+    args: Vec<String>,
+    mode: LaunchMode,
+    /// Keep the launcher open after this launch whatever the preference says
+    stay_open: bool,
+}
 
 pub(crate) struct QuickLaunchApp {
-    root_folder: RootFolder,
+    /// One scanned tree per enabled script root
+    root_folders: Vec<RootFolder>,
+    /// Scan filling in `root_folders`; dropping it cancels the scan
+    scan_task: Option<StreamTask<TreeUpdate>>,
+    /// Entries the providers found by themselves, such as installed applications
+    discovered: Vec<ProviderSection>,
+    discover_task: Option<Task<Vec<ProviderSection>>>,
     pick_folder_task: Option<Task<Option<PathBuf>>>,
     app_preferences: AppPreferences,
     usage_stats: UsageStats,
+    search_query: String,
+    /// Grab keyboard focus for the search field on the next frame
+    focus_search: bool,
+    /// Index of the keyboard-selected button, counted across all visible sections
+    selected: usize,
+    /// Launch waiting for the user to confirm it
+    pending_confirm: Option<PendingLaunch>,
+    /// Parameter form for the script about to be launched
+    arg_form: Option<ArgForm>,
+    arg_history: ArgHistory,
+    /// Scripts started without a terminal, newest last
+    background_runs: Vec<BackgroundRun>,
+    /// Reports file changes under the script roots; `None` if they could not be watched
+    watcher: Option<DirWatcher>,
+    /// Changes not yet picked up by a rebuild
+    pending_changes: Vec<PathBuf>,
+    rebuild_task: Option<Task<Vec<TreeUpdate>>>,
+    /// Failures shown in the corner instead of crashing the launcher
+    toasts: Toasts,
+    settings: SettingsWindow,
+    recent: RecentLaunches,
 }
 
 impl QuickLaunchApp {
     pub fn new(cc: &eframe::CreationContext<'_>, app_preferences: AppPreferences) -> Self {
-        let target_directory = app_preferences
-            .script_dir
-            .clone()
-            .unwrap_or_else(|| app_preferences.default_script_dir.clone());
-
-        let usage_stats = UsageStats::load();
-        let mut root_folder = build_tree(&target_directory);
-        root_folder.sort_by_usage(&|path| usage_stats.get(path));
+        let (usage_stats, migrated) = UsageStats::load();
+        let watcher = watch_roots(&app_preferences, &cc.egui_ctx);
 
         cc.egui_ctx.set_fonts(setup_fonts());
-        QuickLaunchApp {
-            root_folder,
+        cc.egui_ctx.set_theme(app_preferences.theme);
+        let mut app = QuickLaunchApp {
+            root_folders: Vec::new(),
+            scan_task: None,
+            discovered: Vec::new(),
+            discover_task: None,
             pick_folder_task: None,
             app_preferences,
             usage_stats,
-        }
+            search_query: String::new(),
+            focus_search: true,
+            selected: 0,
+            pending_confirm: None,
+            arg_form: None,
+            arg_history: ArgHistory::load(),
+            background_runs: Vec::new(),
+            watcher,
+            pending_changes: Vec::new(),
+            rebuild_task: None,
+            toasts: Toasts::default(),
+            settings: SettingsWindow::default(),
+            recent: RecentLaunches::default(),
+        };
+        app.toasts.report(migrated);
+        app.start_scan();
+        app
     }
 
-    fn save_preferences(&self) {
-        self.app_preferences.save()
+    fn save_preferences(&mut self) {
+        let result = self.app_preferences.save();
+        self.toasts.report(result);
     }
 
-    fn exit_application(&self, ui: &mut egui::Ui) {
-        ui.ctx().request_repaint();
-        ui.ctx().send_viewport_cmd(egui::ViewportCommand::Close);
+    fn exit_application(&self, ctx: &egui::Context) {
+        ctx.request_repaint();
+        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
     }
 
-    fn get_script_dir(&self) -> &Path {
-        self.app_preferences
-            .script_dir
-            .as_deref()
-            .unwrap_or(&self.app_preferences.default_script_dir)
+    fn dialog_open(&self) -> bool {
+        self.pending_confirm.is_some() || self.arg_form.is_some() || self.settings.open
     }
 
-    fn set_script_dir(&mut self, path: PathBuf) {
-        self.app_preferences.script_dir = Some(path);
-        self.save_preferences();
+    /// Add a script root unless it is already configured, saying whether it was added; the caller
+    /// saves the preferences
+    fn add_root(&mut self, path: PathBuf) -> bool {
+        if self
+            .app_preferences
+            .roots
+            .iter()
+            .any(|root| root.path == path)
+        {
+            return false;
+        }
+        self.app_preferences.roots.push(ScriptRoot::new(path));
+        true
     }
 
-    fn script_dir_component(&mut self, ui: &mut egui::Ui) {
-        ui.horizontal_centered(|ui| {
-            let open_folder_target: &Path = self.get_script_dir();
-            let script_dir_text = self.get_script_dir();
-
-            ui.label(script_dir_text.to_string_lossy());
-            if folder_button(ui)
-                .on_hover_text("Open Script Folder")
-                .clicked()
-            {
-                open_native_file_viewer(open_folder_target).expect("Failed to open directory");
+    /// One chip per script root with a toggle and an open-folder button, plus a button for adding
+    /// another root. Right-click a chip to remove its root.
+    fn roots_component(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal_wrapped(|ui| {
+            let mut changed = false;
+            let mut remove = None;
+            for (index, root) in self.app_preferences.roots.iter_mut().enumerate() {
+                let mut label = egui::RichText::new(root.label.as_str());
+                if let Some([r, g, b]) = root.color {
+                    label = label.color(egui::Color32::from_rgb(r, g, b));
+                }
+                let response = ui
+                    .checkbox(&mut root.enabled, label)
+                    .on_hover_text(root.path.to_string_lossy());
+                changed |= response.changed();
+                response.context_menu(|ui| {
+                    if ui.button("Remove root").clicked() {
+                        remove = Some(index);
+                        ui.close_menu();
+                    }
+                });
+                if folder_button(ui)
+                    .on_hover_text("Open Script Folder")
+                    .clicked()
+                {
+                    self.toasts.report(open_native_file_viewer(&root.path));
+                }
+                ui.separator();
+            }
+            if let Some(index) = remove {
+                self.app_preferences.roots.remove(index);
+                changed = true;
             }
+
             if folder_open_dialog(ui)
-                .on_hover_text("Pick Script Folder")
+                .on_hover_text("Add Script Folder")
                 .clicked()
                 && self.pick_folder_task.is_none()
             {
                 self.pick_folder_task = Some(pick_folder_async());
             }
+            self.hidden_menu(ui);
             if let Some(task) = &mut self.pick_folder_task
                 && let Some(new_dir) = task.try_take()
             {
                 if let Some(new_dir) = new_dir {
-                    self.set_script_dir(new_dir);
-                    self.rescan_dir();
+                    changed |= self.add_root(new_dir);
                 }
                 self.pick_folder_task = None;
             }
+
+            if changed {
+                self.save_preferences();
+                self.rescan_roots(ui.ctx());
+            }
+        });
+    }
+
+    /// Menu listing the hidden entries, each with a button to show it again
+    fn hidden_menu(&mut self, ui: &mut egui::Ui) {
+        let hidden = &self.app_preferences.hidden;
+        if hidden.is_empty() {
+            return;
+        }
+        let mut unhide = None;
+        ui.menu_button(format!("Hidden ({})", hidden.len()), |ui| {
+            for (index, id) in hidden.iter().enumerate() {
+                if ui
+                    .button(format!("Show {}", self.entry_label(id)))
+                    .on_hover_text(id.to_string())
+                    .clicked()
+                {
+                    unhide = Some(index);
+                    ui.close_menu();
+                }
+            }
         });
+        if let Some(index) = unhide {
+            self.app_preferences.hidden.remove(index);
+            self.save_preferences();
+        }
     }
 
-    /// Renders top panel showing the Location of the script directory, and buttons for modifying or
-    /// viewing
+    /// Renders top panel showing the search field and the script roots, with buttons for modifying
+    /// or viewing them
     fn top_panel(&mut self, ctx: &egui::Context) {
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
-            self.script_dir_component(ui);
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if settings_button(ui).on_hover_text("Settings").clicked() {
+                    self.settings.open = !self.settings.open;
+                }
+                self.scan_status(ui);
+                self.search_component(ui);
+            });
+            self.roots_component(ui);
         });
     }
 
-    fn action_panel(&mut self, ctx: &egui::Context) {
+    /// Spinner with the number of scripts found so far while a scan is running, and a button to
+    /// stop it
+    fn scan_status(&mut self, ui: &mut egui::Ui) {
+        let Some(task) = &self.scan_task else {
+            return;
+        };
+        if close_button(ui).on_hover_text("Stop scanning").clicked() {
+            task.cancel();
+        }
+        let found: usize = self.root_folders.iter().map(RootFolder::entry_count).sum();
+        ui.label(format!("{found} scripts"));
+        ui.spinner().on_hover_text("Scanning script folders");
+    }
+
+    /// Search field that narrows the launcher grid as you type
+    fn search_component(&mut self, ui: &mut egui::Ui) {
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut self.search_query)
+                .hint_text("Search scripts")
+                .desired_width(f32::INFINITY),
+        );
+        if response.changed() {
+            self.selected = 0;
+        }
+        if self.focus_search {
+            response.request_focus();
+            self.focus_search = false;
+        }
+    }
+
+    fn action_panel(&mut self, ctx: &egui::Context, nav_keys: Vec<NavKey>) {
         egui::CentralPanel::default().show(ctx, |ui| {
             let spacing = ui.style().spacing.item_spacing.x;
             let total_width = ui.available_width();
             let num_cols = self.app_preferences.num_cols.get();
             let button_width = (total_width - spacing * 2.0) / num_cols as f32;
-            let button_size = egui::vec2(button_width, 32.0);
-
-            let num_rows = self.root_folder.entries.len().div_ceil(num_cols);
-            egui::Grid::new("root")
-                .spacing(egui::vec2(spacing, spacing))
-                .show(ui, |ui| {
-                    for row in 0..num_rows {
-                        for col in 0..num_cols {
-                            let index = row * num_cols + col;
-                            if let Some(entry) = self.root_folder.entries.get(index) {
-                                let tooltip = entry.executable_path.to_string_lossy();
-                                if ui
-                                    .add_sized(button_size, egui::Button::new(&entry.name))
-                                    .on_hover_text(tooltip.as_ref())
-                                    .clicked()
-                                {
-                                    self.usage_stats.increment(&entry.executable_path);
-                                    spawn_script_in_terminal(&entry.executable_path)
-                                        .expect("Failed to spawn script in terminal");
-                                    self.exit_application(ui);
-                                }
-                            }
-                        }
-                        ui.end_row();
+            let button_size = egui::vec2(button_width, self.app_preferences.button_height);
+
+            scan_warnings(ui, &self.root_folders);
+            if let Some(id) = self.recent.strip(ui) {
+                self.launch_script(&id, None, true, ui.ctx());
+            }
+            self.recent.request_repaint(ui.ctx());
+
+            if self.search_query.trim().is_empty()
+                && self.app_preferences.folder_layout == FolderLayout::Tree
+            {
+                self.expand_buttons(ui);
+            }
+            let query = self.search_query.trim();
+
+            let mut sections = grid_sections(
+                ui,
+                &self.root_folders,
+                &self.discovered,
+                &self.app_preferences,
+                query,
+                &|id| {
+                    self.usage_stats
+                        .frecency(id, self.app_preferences.usage_half_life_days)
+                },
+            );
+
+            for cell in sections.iter_mut().flat_map(|section| &mut section.cells) {
+                cell.flash = self.recent.flash(cell.id);
+            }
+
+            let section_lens: Vec<usize> = sections.iter().map(|s| s.cells.len()).collect();
+            let total: usize = section_lens.iter().sum();
+            let scroll_to_selected = !nav_keys.is_empty();
+            let mut launch_index = None;
+            for key in nav_keys {
+                match key {
+                    NavKey::Move(direction) => {
+                        self.selected = step(&section_lens, num_cols, self.selected, direction)
                     }
-                });
+                    NavKey::Activate => launch_index = Some(self.selected),
+                    NavKey::LaunchNth(n) => launch_index = Some(n),
+                    NavKey::Close => self.exit_application(ui.ctx()),
+                }
+            }
+            self.selected = self.selected.min(total.saturating_sub(1));
 
-            for folder_idx in 0..self.root_folder.folders.len() {
-                if self.root_folder.folders[folder_idx].flat_entries.is_empty() {
-                    continue;
+            let mut event = launch_index.and_then(|index| {
+                sections
+                    .iter()
+                    .flat_map(|section| section.cells.iter())
+                    .nth(index)
+                    .filter(|cell| !cell.missing)
+                    .map(|cell| GridEvent::Launch {
+                        id: cell.id.clone(),
+                        modifiers: egui::Modifiers::NONE,
+                    })
+            });
+
+            let mut toggled = None;
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                let mut offset = 0;
+                for section in sections {
+                    if let Some(header) = &section.header
+                        && section_header(ui, header)
+                        && let SectionHeader::TreeFolder { path, .. } = header
+                    {
+                        toggled = Some(path.clone());
+                    }
+                    let len = section.cells.len();
+                    let selected = self.selected.checked_sub(offset).filter(|i| *i < len);
+                    let section_event = entry_grid(
+                        ui,
+                        section,
+                        button_size,
+                        num_cols,
+                        selected,
+                        scroll_to_selected,
+                    );
+                    if event.is_none() {
+                        event = section_event;
+                    }
+                    offset += len;
                 }
+            });
 
-                ui.separator();
-                let padding = egui::vec2(8.0, 4.0);
-                let folder_name = self.root_folder.folders[folder_idx].name.clone();
-                let galley = ui.painter().layout_no_wrap(
-                    folder_name.clone(),
-                    egui::FontId::default(),
-                    egui::Color32::WHITE,
+            if let Some(path) = toggled {
+                self.toggle_collapsed(path);
+            }
+            match event {
+                Some(GridEvent::Launch { id, modifiers }) => {
+                    // Ctrl-click runs in the background regardless of the script's own preference;
+                    // Shift-click keeps the launcher open regardless of the global one
+                    let mode = modifiers.command.then_some(LaunchMode::Background);
+                    self.launch_script(&id, mode, modifiers.shift, ui.ctx());
+                }
+                Some(GridEvent::Action(id, action)) => self.handle_action(id, action, ui.ctx()),
+                Some(GridEvent::Move { from, to }) => {
+                    let favorites = &mut self.app_preferences.favorites;
+                    if from < favorites.len() && to < favorites.len() {
+                        let id = favorites.remove(from);
+                        favorites.insert(to, id);
+                        self.save_preferences();
+                    }
+                }
+                None => {}
+            }
+        });
+    }
+
+    /// "Expand all" and "Collapse all" for the nested folder layout
+    fn expand_buttons(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.small_button("Expand all").clicked() {
+                self.app_preferences.collapsed_folders.clear();
+                self.save_preferences();
+            }
+            if ui.small_button("Collapse all").clicked() {
+                self.app_preferences.collapsed_folders = self
+                    .root_folders
+                    .iter()
+                    .flat_map(RootFolder::folder_paths)
+                    .collect();
+                self.save_preferences();
+            }
+        });
+    }
+
+    fn toggle_collapsed(&mut self, path: PathBuf) {
+        let collapsed = &mut self.app_preferences.collapsed_folders;
+        if let Some(index) = collapsed.iter().position(|folder| *folder == path) {
+            collapsed.remove(index);
+        } else {
+            collapsed.push(path);
+        }
+        self.save_preferences();
+    }
+
+    fn toggle_pin(&mut self, id: EntryId) {
+        let favorites = &mut self.app_preferences.favorites;
+        if let Some(index) = favorites.iter().position(|pinned| *pinned == id) {
+            favorites.remove(index);
+        } else {
+            favorites.push(id);
+        }
+        self.save_preferences();
+    }
+
+    /// The entry called `id`, among the scanned roots or the discovered entries
+    fn find_entry(&self, id: &EntryId) -> Option<&Entry> {
+        find_entry(&self.root_folders, id).or_else(|| find_discovered(&self.discovered, id))
+    }
+
+    /// Friendly name of the entry `id`, falling back to the last part of its id while it isn't
+    /// in the tree
+    fn entry_label(&self, id: &EntryId) -> String {
+        match self.find_entry(id) {
+            Some(entry) => entry.label().to_owned(),
+            None => {
+                let key = Path::new(id.key());
+                key.file_name()
+                    .unwrap_or(key.as_os_str())
+                    .to_string_lossy()
+                    .into_owned()
+            }
+        }
+    }
+
+    /// Launch `id`, first collecting its parameters or asking for confirmation if its header
+    /// requests it. `mode` overrides the launch mode declared by the entry; `stay_open` keeps the
+    /// launcher open even if the preference says otherwise. Entries no longer in the tree are
+    /// left alone.
+    fn launch_script(
+        &mut self,
+        id: &EntryId,
+        mode: Option<LaunchMode>,
+        stay_open: bool,
+        ctx: &egui::Context,
+    ) {
+        let Some(entry) = self.find_entry(id) else {
+            return;
+        };
+        let meta = &entry.meta;
+        let mode = mode.unwrap_or_else(|| LaunchMode::declared_by(meta));
+        if !meta.args.is_empty() {
+            self.open_arg_form(id, meta.args.clone(), mode, stay_open);
+        } else if meta.confirm {
+            self.pending_confirm = Some(PendingLaunch {
+                id: id.clone(),
+                args: Vec::new(),
+                mode,
+                stay_open,
+            });
+        } else {
+            self.run_script(id, &[], mode, stay_open, ctx);
+        }
+    }
+
+    fn open_arg_form(
+        &mut self,
+        id: &EntryId,
+        args: Vec<ScriptArg>,
+        mode: LaunchMode,
+        stay_open: bool,
+    ) {
+        let values = self.arg_history.initial_values(id, &args);
+        self.arg_form = Some(ArgForm::new(
+            id.clone(),
+            self.entry_label(id),
+            args,
+            values,
+            mode,
+            stay_open,
+        ));
+    }
+
+    /// Open the parameter form even for entries that declare no parameters, offering a single
+    /// free-text field instead
+    fn prompt_arguments(&mut self, id: &EntryId) {
+        let meta = self.find_entry(id).map(|entry| &entry.meta);
+        let mode = meta.map_or(LaunchMode::Terminal, LaunchMode::declared_by);
+        let args = match meta {
+            Some(meta) if !meta.args.is_empty() => meta.args.clone(),
+            _ => vec![ScriptArg {
+                name: "arguments".to_owned(),
+                kind: ArgKind::Words,
+                default: String::new(),
+                help: Some("Separated by spaces; quote to keep spaces in one argument".to_owned()),
+            }],
+        };
+        self.open_arg_form(id, args, mode, false);
+    }
+
+    /// The file the entry `id` was read from; while it isn't in the tree, the file its id names
+    fn entry_path(&self, id: &EntryId) -> PathBuf {
+        self.find_entry(id)
+            .map_or_else(|| PathBuf::from(id.key()), |entry| entry.path.clone())
+    }
+
+    fn handle_action(&mut self, id: EntryId, action: CellAction, ctx: &egui::Context) {
+        match action {
+            CellAction::RunInTerminal => {
+                self.launch_script(&id, Some(LaunchMode::Terminal), false, ctx)
+            }
+            CellAction::RunInBackground => {
+                self.launch_script(&id, Some(LaunchMode::Background), false, ctx)
+            }
+            CellAction::RunWithArguments => self.prompt_arguments(&id),
+            CellAction::OpenInEditor => {
+                let (profile, _) = self.app_preferences.terminal_for(None);
+                let file = self.entry_path(&id);
+                self.toasts.report(open_in_editor(&file, &profile));
+            }
+            CellAction::RevealInFileManager => {
+                let path = self.entry_path(&id);
+                let folder = path.parent().unwrap_or(&path);
+                self.toasts.report(open_native_file_viewer(folder));
+            }
+            CellAction::CopyPath => {
+                ctx.copy_text(self.entry_path(&id).to_string_lossy().into_owned())
+            }
+            CellAction::CopyCommandLine => {
+                if let Some(entry) = self.find_entry(&id) {
+                    ctx.copy_text(entry.command(&[]).command_line());
+                }
+            }
+            CellAction::TogglePin => self.toggle_pin(id),
+            CellAction::Hide => {
+                self.app_preferences
+                    .favorites
+                    .retain(|pinned| *pinned != id);
+                self.app_preferences.hidden.push(id);
+                self.save_preferences();
+            }
+            CellAction::ResetUsage => {
+                let result = self.usage_stats.reset(&id);
+                self.toasts.report(result);
+                self.sort_entries();
+            }
+        }
+    }
+
+    fn sort_entries(&mut self) {
+        for root_folder in &mut self.root_folders {
+            sort_root_folder(root_folder, &self.app_preferences, &self.usage_stats);
+        }
+        for section in &mut self.discovered {
+            sort_section(section, &self.app_preferences, &self.usage_stats);
+        }
+    }
+
+    /// Start the entry and record the launch. After a terminal or detached launch the launcher
+    /// exits unless `stay_open` or the stay-open preference is set.
+    fn run_script(
+        &mut self,
+        id: &EntryId,
+        args: &[String],
+        mode: LaunchMode,
+        stay_open: bool,
+        ctx: &egui::Context,
+    ) {
+        let Some(entry) = self.find_entry(id).cloned() else {
+            return;
+        };
+        let command = entry.command(args);
+        let label = entry.label().to_owned();
+        match mode {
+            LaunchMode::Terminal | LaunchMode::Detached => {
+                let (profile, keep_open) = self.app_preferences.terminal_for(Some(&entry.meta));
+                let result = if mode == LaunchMode::Detached {
+                    spawn_detached(&command)
+                } else {
+                    spawn_in_terminal(&command, &profile, keep_open)
+                };
+                if let Err(error) = result {
+                    // Stay open so the failure can be read
+                    self.toasts.error(&error);
+                    return;
+                }
+                let result = self.usage_stats.increment(id);
+                self.toasts.report(result);
+                if stay_open || self.app_preferences.stay_open {
+                    self.recent.record(id, label);
+                } else {
+                    self.exit_application(ctx);
+                }
+            }
+            LaunchMode::Background => {
+                let result = self.usage_stats.increment(id);
+                self.toasts.report(result);
+                self.recent.record(id, label.clone());
+                let run = spawn_background(&entry.path, &command, label.clone())
+                    .unwrap_or_else(|err| BackgroundRun::failed_to_start(&entry.path, label, &err));
+                self.background_runs.push(run);
+            }
+        }
+    }
+
+    /// Ask before running a script whose header carries `ql:confirm`.
+    ///
+    /// While the dialog is open it takes all navigation keys; otherwise they are handed back.
+    fn confirm_modal(&mut self, ctx: &egui::Context, nav_keys: Vec<NavKey>) -> Vec<NavKey> {
+        let Some(pending) = self.pending_confirm.clone() else {
+            return nav_keys;
+        };
+        let label = self.entry_label(&pending.id);
+
+        let mut run = nav_keys.contains(&NavKey::Activate);
+        let mut cancel = nav_keys.contains(&NavKey::Close);
+        egui::Modal::new(egui::Id::new("confirm_launch")).show(ctx, |ui| {
+            ui.label(format!("Run {label}?"));
+            ui.horizontal(|ui| {
+                run |= ui.button("Run").clicked();
+                cancel |= ui.button("Cancel").clicked();
+            });
+            if run {
+                self.pending_confirm = None;
+                self.run_script(
+                    &pending.id,
+                    &pending.args,
+                    pending.mode,
+                    pending.stay_open,
+                    ui.ctx(),
                 );
-                let (rect, _) =
-                    ui.allocate_exact_size(galley.size() + padding * 2.0, egui::Sense::hover());
-                ui.painter()
-                    .rect_filled(rect, 4.0, egui::Color32::from_gray(40));
-                ui.painter().text(
-                    rect.center(),
-                    egui::Align2::CENTER_CENTER,
-                    &folder_name,
-                    egui::FontId::default(),
-                    egui::Color32::WHITE,
+            } else if cancel {
+                self.pending_confirm = None;
+            }
+        });
+        Vec::new()
+    }
+
+    /// Show the parameter form, launching the script once it is submitted, or first asking for
+    /// confirmation if its header requests it.
+    ///
+    /// While the form is open it takes all navigation keys; otherwise they are handed back.
+    fn arg_form_modal(&mut self, ctx: &egui::Context, nav_keys: Vec<NavKey>) -> Vec<NavKey> {
+        let Some(form) = &mut self.arg_form else {
+            return nav_keys;
+        };
+        match form.show(ctx, &nav_keys) {
+            FormOutcome::Open => {}
+            FormOutcome::Cancel => self.arg_form = None,
+            FormOutcome::Submit(argv) => {
+                if let Some(form) = self.arg_form.take() {
+                    let result = self
+                        .arg_history
+                        .remember(&form.id, &form.args, &form.values);
+                    self.toasts.report(result);
+                    if self
+                        .find_entry(&form.id)
+                        .is_some_and(|entry| entry.meta.confirm)
+                    {
+                        self.pending_confirm = Some(PendingLaunch {
+                            id: form.id,
+                            args: argv,
+                            mode: form.mode,
+                            stay_open: form.stay_open,
+                        });
+                    } else {
+                        self.run_script(&form.id, &argv, form.mode, form.stay_open, ctx);
+                    }
+                }
+            }
+        }
+        Vec::new()
+    }
+
+    /// Rescan every enabled root from scratch, e.g. after the list of roots changed
+    fn rescan_roots(&mut self, ctx: &egui::Context) {
+        let roots = &self.app_preferences.roots;
+        let result = self.usage_stats.prune(|id| {
+            provider(id.provider()).is_some_and(|provider| provider.keeps_history(id, roots))
+        });
+        self.toasts.report(result);
+        self.start_scan();
+        self.watcher = watch_roots(&self.app_preferences, ctx);
+        self.pending_changes.clear();
+        self.rebuild_task = None;
+    }
+
+    /// Show the tree from the scan cache, or an empty one for roots it doesn't cover, and bring
+    /// it up to date from a scan on a background thread, cancelling any scan still running.
+    /// The providers look for entries of their own alongside.
+    fn start_scan(&mut self) {
+        let preferences = self.app_preferences.clone();
+        self.discover_task = Some(Task::spawn(move || discover(&preferences)));
+        let roots: Vec<ScriptRoot> = self.app_preferences.enabled_roots().cloned().collect();
+        let options = self.app_preferences.scan.clone();
+        let mut cache = ScanCache::load();
+        let cached: Vec<Option<RootFolder>> = roots
+            .iter()
+            .map(|root| cache.take_root(root, &options))
+            .collect();
+        self.root_folders = roots
+            .iter()
+            .zip(&cached)
+            .map(|(root, cached)| {
+                cached
+                    .clone()
+                    .unwrap_or_else(|| RootFolder::unscanned(root))
+            })
+            .collect();
+        self.sort_entries();
+        self.scan_task = Some(StreamTask::spawn(move |progress| {
+            for (root, cached) in roots.iter().zip(&cached) {
+                scan_root(
+                    root,
+                    &options,
+                    cached.as_ref(),
+                    progress.cancelled(),
+                    &mut |update| progress.send(update),
                 );
+            }
+        }));
+    }
+
+    /// Add whatever the running scan has found since the last frame
+    fn poll_scan(&mut self, ctx: &egui::Context) {
+        let Some(task) = &mut self.scan_task else {
+            return;
+        };
+        let updates = task.try_parts();
+        let finished = task.is_finished();
+        let complete = finished && !task.is_cancelled();
+        if finished {
+            self.scan_task = None;
+        } else {
+            ctx.request_repaint_after(REBUILD_POLL_INTERVAL);
+        }
+        for update in updates {
+            self.apply_update(update);
+        }
+        // A stopped scan leaves the tree partly stale, which the cache must not remember
+        if complete {
+            let cache = ScanCache::new(&self.app_preferences.scan, &self.root_folders);
+            self.toasts.report(cache.save());
+        }
+    }
+
+    /// Show what the providers found by themselves once they are done looking
+    fn poll_discovered(&mut self, ctx: &egui::Context) {
+        let Some(task) = &mut self.discover_task else {
+            return;
+        };
+        let Some(discovered) = task.try_take() else {
+            ctx.request_repaint_after(REBUILD_POLL_INTERVAL);
+            return;
+        };
+        self.discovered = discovered;
+        self.discover_task = None;
+        for section in &mut self.discovered {
+            sort_section(section, &self.app_preferences, &self.usage_stats);
+        }
+    }
+
+    /// Merge a freshly read part of the tree into its root and re-sort that root
+    fn apply_update(&mut self, update: TreeUpdate) {
+        if let Some(root_folder) = self
+            .root_folders
+            .iter_mut()
+            .find(|root_folder| root_folder.path == update.root)
+        {
+            root_folder.apply(update);
+            sort_root_folder(root_folder, &self.app_preferences, &self.usage_stats);
+        }
+    }
+
+    /// Pick up file changes reported by the watcher and rebuild the affected folders off the UI
+    /// thread, one rebuild at a time. Changes seen during a full scan wait for it to finish.
+    fn poll_watcher(&mut self, ctx: &egui::Context) {
+        if let Some(changes) = self.watcher.as_ref().and_then(DirWatcher::try_changes) {
+            self.pending_changes.extend(changes);
+        }
+
+        if let Some(task) = &mut self.rebuild_task {
+            let Some(updates) = task.try_take() else {
+                ctx.request_repaint_after(REBUILD_POLL_INTERVAL);
+                return;
+            };
+            for update in updates {
+                self.apply_update(update);
+            }
+            self.rebuild_task = None;
+        }
+
+        if !self.pending_changes.is_empty() && self.scan_task.is_none() {
+            let root_paths: Vec<PathBuf> = self
+                .root_folders
+                .iter()
+                .map(|root_folder| root_folder.path.clone())
+                .collect();
+            let changed = std::mem::take(&mut self.pending_changes);
+            let options = self.app_preferences.scan.clone();
+            self.rebuild_task = Some(Task::spawn(move || {
+                root_paths
+                    .iter()
+                    .filter(|root| changed.iter().any(|path| path.starts_with(root)))
+                    .map(|root| rebuild_changed(root, &changed, &options))
+                    .collect()
+            }));
+            ctx.request_repaint_after(REBUILD_POLL_INTERVAL);
+        }
+    }
+}
+
+/// One line per root whose scan hit a limit, listing the details on hover
+fn scan_warnings(ui: &mut egui::Ui, root_folders: &[RootFolder]) {
+    for root in root_folders.iter().filter(|root| !root.warnings.is_empty()) {
+        let count = |matches: fn(&ScanWarning) -> bool| {
+            root.warnings
+                .iter()
+                .filter(|warning| matches(warning))
+                .count()
+        };
+        let mut reasons = Vec::new();
+        let too_deep = count(|warning| matches!(warning, ScanWarning::TooDeep { .. }));
+        if too_deep > 0 {
+            reasons.push(format!("{too_deep} folders cut off at the depth limit"));
+        }
+        let loops = count(|warning| matches!(warning, ScanWarning::Loop { .. }));
+        if loops > 0 {
+            reasons.push(format!("{loops} folder loops skipped"));
+        }
+        if count(|warning| matches!(warning, ScanWarning::TooManyEntries { .. })) > 0 {
+            reasons.push("entry limit reached".to_owned());
+        }
+        let details: Vec<String> = root
+            .warnings
+            .iter()
+            .take(MAX_LISTED_WARNINGS)
+            .map(ToString::to_string)
+            .collect();
+        ui.colored_label(
+            ui.visuals().warn_fg_color,
+            format!("⚠ {} is incomplete: {}", root.label, reasons.join(", ")),
+        )
+        .on_hover_text(details.join("\n"));
+    }
+}
 
-                let folder_rows = self.root_folder.folders[folder_idx]
-                    .flat_entries
-                    .len()
-                    .div_ceil(num_cols);
-                egui::Grid::new(&folder_name)
-                    .spacing(egui::vec2(spacing, spacing))
-                    .show(ui, |ui| {
-                        for row in 0..folder_rows {
-                            for col in 0..num_cols {
-                                let index = row * num_cols + col;
-                                if let Some(entry) =
-                                    self.root_folder.folders[folder_idx].flat_entries.get(index)
-                                {
-                                    let tooltip = entry.executable_path.to_string_lossy();
-                                    if ui
-                                        .add_sized(
-                                            button_size,
-                                            egui::Button::new(&entry.display_name),
-                                        )
-                                        .on_hover_text(tooltip.as_ref())
-                                        .clicked()
-                                    {
-                                        self.usage_stats.increment(&entry.executable_path);
-                                        spawn_script_in_terminal(&entry.executable_path)
-                                            .expect("Failed to spawn script in terminal");
-                                        self.exit_application(ui);
-                                    }
-                                }
-                            }
-                            ui.end_row();
+/// Order a root's entries as configured
+fn sort_root_folder(
+    root_folder: &mut RootFolder,
+    preferences: &AppPreferences,
+    usage_stats: &UsageStats,
+) {
+    match preferences.sort_mode {
+        SortMode::Usage => root_folder
+            .sort_by_usage(&|id| usage_stats.frecency(id, preferences.usage_half_life_days)),
+        SortMode::Name => root_folder.sort_by_name(),
+    }
+}
+
+/// Order the entries of a discovered section as configured
+fn sort_section(
+    section: &mut ProviderSection,
+    preferences: &AppPreferences,
+    usage_stats: &UsageStats,
+) {
+    let entries = &mut section.entries;
+    match preferences.sort_mode {
+        SortMode::Usage => entries.sort_by(|a, b| {
+            let score = |id| usage_stats.frecency(id, preferences.usage_half_life_days);
+            score(&b.id).total_cmp(&score(&a.id))
+        }),
+        SortMode::Name => entries.sort_by_cached_key(|entry| entry.label().to_lowercase()),
+    }
+}
+
+/// Watch every enabled root, repainting the UI when something in one of them changes
+fn watch_roots(preferences: &AppPreferences, ctx: &egui::Context) -> Option<DirWatcher> {
+    let ctx = ctx.clone();
+    let dirs = preferences.enabled_roots().map(|root| root.path.as_path());
+    DirWatcher::new(dirs, move || ctx.request_repaint()).ok()
+}
+
+/// Group the visible entries into grids: the pinned favorites, each root's own entries, then the
+/// folders, and last one grid per provider that found entries by itself, or a single grid of
+/// ranked hits while searching. Folders
+/// get one grid each listing everything below them, or in the nested layout one collapsible grid
+/// per folder at every level. Hidden entries are left out everywhere. Root headers are only shown
+/// when more than one section of that level is.
+fn grid_sections<'a>(
+    ui: &egui::Ui,
+    root_folders: &'a [RootFolder],
+    discovered: &'a [ProviderSection],
+    preferences: &'a AppPreferences,
+    query: &str,
+    usage: &dyn Fn(&EntryId) -> f64,
+) -> Vec<GridSection<'a>> {
+    let favorites = &preferences.favorites;
+    let is_hidden = |id: &EntryId| preferences.hidden.contains(id);
+    if !query.is_empty() {
+        let hits = search(root_folders, discovered, query, usage);
+        return vec![GridSection {
+            id: egui::Id::new("search"),
+            header: None,
+            cells: hits
+                .iter()
+                .filter(|hit| !is_hidden(hit.id))
+                .map(|hit| {
+                    GridCell::new(ui, hit.label, &hit.indices, hit.id, hit.path, hit.meta)
+                        .pinned(favorites.contains(hit.id))
+                })
+                .collect(),
+            reorderable: false,
+        }];
+    }
+
+    let is_pinned = |id: &EntryId| favorites.contains(id);
+    let show_root_headers =
+        root_folders.len() > 1 || !favorites.is_empty() || !discovered.is_empty();
+    let mut sections = Vec::new();
+    if !favorites.is_empty() {
+        sections.push(GridSection {
+            id: egui::Id::new("favorites"),
+            header: Some(SectionHeader::Root("Favorites", None)),
+            cells: favorites
+                .iter()
+                .map(|id| {
+                    match find_entry(root_folders, id).or_else(|| find_discovered(discovered, id)) {
+                        Some(entry) => {
+                            GridCell::new(ui, entry.label(), &[], id, &entry.path, &entry.meta)
+                                .pinned(true)
                         }
-                    });
+                        None => GridCell::missing(id),
+                    }
+                })
+                .collect(),
+            reorderable: true,
+        });
+    }
+    for root in root_folders {
+        sections.push(GridSection {
+            id: egui::Id::new(("root", &root.path)),
+            header: show_root_headers.then_some(SectionHeader::Root(&root.label, root.color)),
+            cells: root
+                .entries
+                .iter()
+                .filter(|entry| !is_hidden(&entry.id))
+                .map(|entry| {
+                    GridCell::new(ui, entry.label(), &[], &entry.id, &entry.path, &entry.meta)
+                        .pinned(is_pinned(&entry.id))
+                })
+                .collect(),
+            reorderable: false,
+        });
+        if preferences.folder_layout == FolderLayout::Tree {
+            tree_sections(ui, preferences, &root.path, &root.folders, 0, &mut sections);
+            continue;
+        }
+        for folder in &root.folders {
+            let cells: Vec<GridCell> = folder
+                .flat_entries
+                .iter()
+                .filter(|entry| !is_hidden(&entry.id))
+                .map(|entry| {
+                    GridCell::new(
+                        ui,
+                        &entry.display_name,
+                        &[],
+                        &entry.id,
+                        &entry.path,
+                        &entry.meta,
+                    )
+                    .pinned(is_pinned(&entry.id))
+                })
+                .collect();
+            if cells.is_empty() {
+                continue;
             }
+            sections.push(GridSection {
+                id: egui::Id::new(("folder", &root.path, &folder.name)),
+                header: Some(SectionHeader::Folder(&folder.name)),
+                cells,
+                reorderable: false,
+            });
+        }
+    }
+    for section in discovered {
+        sections.push(GridSection {
+            id: egui::Id::new(("provider", section.label)),
+            header: Some(SectionHeader::Root(section.label, None)),
+            cells: section
+                .entries
+                .iter()
+                .filter(|entry| !is_hidden(&entry.id))
+                .map(|entry| {
+                    GridCell::new(ui, entry.label(), &[], &entry.id, &entry.path, &entry.meta)
+                        .pinned(is_pinned(&entry.id))
+                })
+                .collect(),
+            reorderable: false,
         });
     }
+    sections
+}
 
-    fn rescan_dir(&mut self) {
-        let script_dir = self.get_script_dir().to_path_buf();
-        self.usage_stats.prune(&script_dir);
-        self.root_folder = build_tree(&script_dir);
-        self.root_folder
-            .sort_by_usage(&|path| self.usage_stats.get(path));
+/// One section per folder below `parent`, each holding only the folder's own entries and followed
+/// by its subfolders unless it is collapsed. Folders with nothing visible below them are left out.
+fn tree_sections<'a>(
+    ui: &egui::Ui,
+    preferences: &'a AppPreferences,
+    parent: &Path,
+    folders: &'a [Folder],
+    depth: usize,
+    sections: &mut Vec<GridSection<'a>>,
+) {
+    let is_hidden = |id: &EntryId| preferences.hidden.contains(id);
+    let is_pinned = |id: &EntryId| preferences.favorites.contains(id);
+    for folder in folders {
+        if folder.flat_entries.iter().all(|entry| is_hidden(&entry.id)) {
+            continue;
+        }
+        let path = parent.join(&folder.name);
+        let open = !preferences.collapsed_folders.contains(&path);
+        let cells = if open {
+            folder
+                .entries
+                .iter()
+                .filter(|entry| !is_hidden(&entry.id))
+                .map(|entry| {
+                    GridCell::new(ui, entry.label(), &[], &entry.id, &entry.path, &entry.meta)
+                        .pinned(is_pinned(&entry.id))
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        sections.push(GridSection {
+            id: egui::Id::new(("tree_folder", &path)),
+            header: Some(SectionHeader::TreeFolder {
+                name: &folder.name,
+                path: path.clone(),
+                depth,
+                open,
+            }),
+            cells,
+            reorderable: false,
+        });
+        if open {
+            tree_sections(ui, preferences, &path, &folder.folders, depth + 1, sections);
+        }
     }
 }
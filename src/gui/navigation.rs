@@ -0,0 +1,217 @@
+use egui::{Key, Modifiers};
+
+const LAUNCH_KEYS: [Key; 9] = [
+    Key::Num1,
+    Key::Num2,
+    Key::Num3,
+    Key::Num4,
+    Key::Num5,
+    Key::Num6,
+    Key::Num7,
+    Key::Num8,
+    Key::Num9,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// A keyboard command for the launcher grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NavKey {
+    Move(Direction),
+    /// Launch the selected cell
+    Activate,
+    /// Launch the n-th visible cell (zero based)
+    LaunchNth(usize),
+    Close,
+}
+
+/// Which keys the launcher takes for itself this frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NavScope {
+    /// Moving around the grid. Left/Right are only claimed when `claim_horizontal` is set so they
+    /// keep moving the text cursor while a search query is being edited.
+    Grid { claim_horizontal: bool },
+    /// A dialog is open; only Enter and Escape are claimed so its own widgets keep Tab and arrows
+    Dialog,
+}
+
+/// Take the navigation keys out of this frame's input before any widget sees them.
+pub fn consume_nav_keys(ctx: &egui::Context, scope: NavScope) -> Vec<NavKey> {
+    ctx.input_mut(|input| {
+        let mut keys = Vec::new();
+        let mut take = |modifiers: Modifiers, key: Key, nav: NavKey| {
+            while input.consume_key(modifiers, key) {
+                keys.push(nav);
+            }
+        };
+
+        take(Modifiers::NONE, Key::Enter, NavKey::Activate);
+        take(Modifiers::NONE, Key::Escape, NavKey::Close);
+        let NavScope::Grid { claim_horizontal } = scope else {
+            return keys;
+        };
+
+        take(Modifiers::NONE, Key::ArrowUp, NavKey::Move(Direction::Up));
+        take(
+            Modifiers::NONE,
+            Key::ArrowDown,
+            NavKey::Move(Direction::Down),
+        );
+        if claim_horizontal {
+            take(
+                Modifiers::NONE,
+                Key::ArrowLeft,
+                NavKey::Move(Direction::Left),
+            );
+            take(
+                Modifiers::NONE,
+                Key::ArrowRight,
+                NavKey::Move(Direction::Right),
+            );
+        }
+        take(Modifiers::SHIFT, Key::Tab, NavKey::Move(Direction::Left));
+        take(Modifiers::NONE, Key::Tab, NavKey::Move(Direction::Right));
+        for (n, key) in LAUNCH_KEYS.into_iter().enumerate() {
+            take(Modifiers::COMMAND, key, NavKey::LaunchNth(n));
+        }
+        keys
+    })
+}
+
+/// Move `index` one step in `direction` across consecutive grid sections of `num_cols` columns.
+///
+/// `sections` holds the number of cells in each section, in render order. Up/Down keep the column
+/// where possible and hop into the neighbouring section at either end; Left/Right walk the cells
+/// in reading order.
+pub fn step(sections: &[usize], num_cols: usize, index: usize, direction: Direction) -> usize {
+    let total: usize = sections.iter().sum();
+    if total == 0 {
+        return 0;
+    }
+    let index = index.min(total - 1);
+
+    // Locate the section and the position inside it
+    let mut start = 0;
+    let mut section = 0;
+    for (i, len) in sections.iter().enumerate() {
+        if index < start + len {
+            section = i;
+            break;
+        }
+        start += len;
+    }
+    let local = index - start;
+    let len = sections[section];
+    let col = local % num_cols;
+
+    match direction {
+        Direction::Left => index.saturating_sub(1),
+        Direction::Right => (index + 1).min(total - 1),
+        Direction::Down => {
+            if local + num_cols < len {
+                return index + num_cols;
+            }
+            if (local / num_cols) < (len - 1) / num_cols {
+                // Short last row below us
+                return start + len - 1;
+            }
+            // Empty sections occupy no cells, so the next non-empty one starts right after us
+            match sections[section + 1..].iter().find(|len| **len > 0) {
+                Some(next_len) => start + len + col.min(next_len - 1),
+                None => index,
+            }
+        }
+        Direction::Up => {
+            if local >= num_cols {
+                return index - num_cols;
+            }
+            match sections[..section].iter().rposition(|len| *len > 0) {
+                Some(prev) => {
+                    let prev_start: usize = sections[..prev].iter().sum();
+                    let prev_len = sections[prev];
+                    let last_row_start = (prev_len - 1) / num_cols * num_cols;
+                    prev_start + (last_row_start + col).min(prev_len - 1)
+                }
+                None => index,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Direction::{Down, Left, Right, Up};
+
+    /// Three columns: a section of five cells (a short second row), an empty one, then three
+    ///
+    /// ```text
+    /// 0 1 2
+    /// 3 4
+    /// 5 6 7
+    /// ```
+    const SECTIONS: [usize; 3] = [5, 0, 3];
+
+    fn steps(index: usize, direction: Direction) -> usize {
+        step(&SECTIONS, 3, index, direction)
+    }
+
+    #[test]
+    fn down_keeps_the_column_and_crosses_into_the_next_section() {
+        assert_eq!(steps(1, Down), 4);
+        // Nothing right below, but a short last row is
+        assert_eq!(steps(2, Down), 4);
+        // The empty section is skipped
+        assert_eq!(steps(3, Down), 5);
+        assert_eq!(steps(4, Down), 6);
+        assert_eq!(steps(7, Down), 7);
+    }
+
+    #[test]
+    fn up_lands_on_the_last_row_of_the_previous_section() {
+        assert_eq!(steps(4, Up), 1);
+        assert_eq!(steps(5, Up), 3);
+        assert_eq!(steps(6, Up), 4);
+        // Past the end of the short row, so its last cell
+        assert_eq!(steps(7, Up), 4);
+        assert_eq!(steps(0, Up), 0);
+        assert_eq!(steps(2, Up), 2);
+    }
+
+    #[test]
+    fn left_and_right_walk_in_reading_order() {
+        assert_eq!(steps(2, Right), 3);
+        assert_eq!(steps(4, Right), 5);
+        assert_eq!(steps(5, Left), 4);
+        assert_eq!(steps(3, Left), 2);
+        assert_eq!(steps(0, Left), 0);
+        assert_eq!(steps(7, Right), 7);
+    }
+
+    #[test]
+    fn single_column_moves_one_cell_at_a_time() {
+        assert_eq!(step(&[2, 2], 1, 1, Down), 2);
+        assert_eq!(step(&[2, 2], 1, 2, Up), 1);
+    }
+
+    #[test]
+    fn empty_grid_stays_at_zero() {
+        for direction in [Up, Down, Left, Right] {
+            assert_eq!(step(&[], 3, 0, direction), 0);
+            assert_eq!(step(&[0, 0], 3, 4, direction), 0);
+        }
+    }
+
+    #[test]
+    fn stale_selection_is_clamped_to_the_last_cell() {
+        assert_eq!(steps(99, Right), 7);
+        assert_eq!(steps(99, Left), 6);
+        assert_eq!(steps(99, Up), 4);
+    }
+}
@@ -0,0 +1,70 @@
+use crate::utils::background::{BackgroundRun, RunStatus, Stream};
+use std::time::Duration;
+
+/// How often to check running scripts for new output
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Bottom panel listing background runs with their live output and exit status
+pub fn output_panel(ctx: &egui::Context, runs: &mut Vec<BackgroundRun>) {
+    if runs.is_empty() {
+        return;
+    }
+    for run in runs.iter_mut() {
+        run.poll();
+    }
+    if runs.iter().any(BackgroundRun::is_running) {
+        ctx.request_repaint_after(POLL_INTERVAL);
+    }
+
+    egui::TopBottomPanel::bottom("output_panel")
+        .resizable(true)
+        .default_height(200.0)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.strong("Output");
+                if ui.button("Clear finished").clicked() {
+                    runs.retain(BackgroundRun::is_running);
+                }
+            });
+            egui::ScrollArea::vertical()
+                .stick_to_bottom(true)
+                .auto_shrink([false, false])
+                .show(ui, |ui| {
+                    let newest = runs.len().saturating_sub(1);
+                    for (index, run) in runs.iter().enumerate() {
+                        egui::CollapsingHeader::new(run.label.as_str())
+                            .id_salt(("background_run", index))
+                            .default_open(index == newest)
+                            .show(ui, |ui| {
+                                for (stream, line) in &run.output {
+                                    let text = egui::RichText::new(line).monospace();
+                                    match stream {
+                                        Stream::Stdout => ui.label(text),
+                                        Stream::Stderr => {
+                                            ui.label(text.color(ui.visuals().error_fg_color))
+                                        }
+                                    };
+                                }
+                            })
+                            .header_response
+                            .on_hover_text(run.path.to_string_lossy());
+                        status_badge(ui, &run.status);
+                    }
+                });
+        });
+}
+
+fn status_badge(ui: &mut egui::Ui, status: &RunStatus) {
+    let (text, color) = match status {
+        RunStatus::Running => ("running".to_owned(), ui.visuals().warn_fg_color),
+        RunStatus::Finished => (
+            "finished (exit 0)".to_owned(),
+            egui::Color32::from_rgb(90, 170, 90),
+        ),
+        RunStatus::Failed(Some(code)) => {
+            (format!("failed (exit {code})"), ui.visuals().error_fg_color)
+        }
+        RunStatus::Failed(None) => ("failed".to_owned(), ui.visuals().error_fg_color),
+    };
+    ui.label(egui::RichText::new(text).small().color(color));
+}
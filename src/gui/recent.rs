@@ -0,0 +1,76 @@
+use crate::utils::provider::EntryId;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// How long a launched script's button stays highlighted
+const FLASH_DURATION: Duration = Duration::from_millis(600);
+/// Scripts listed in the recently launched strip
+const MAX_RECENT: usize = 6;
+
+struct RecentLaunch {
+    id: EntryId,
+    label: String,
+    launched_at: Instant,
+}
+
+/// Scripts launched since the window opened, newest first, so a launcher that stays open shows
+/// what it just did
+#[derive(Default)]
+pub struct RecentLaunches {
+    launches: VecDeque<RecentLaunch>,
+}
+
+impl RecentLaunches {
+    pub fn record(&mut self, id: &EntryId, label: String) {
+        self.launches.retain(|launch| launch.id != *id);
+        self.launches.push_front(RecentLaunch {
+            id: id.clone(),
+            label,
+            launched_at: Instant::now(),
+        });
+        self.launches.truncate(MAX_RECENT);
+    }
+
+    /// How strongly the button for `id` should be highlighted, fading from 1 to 0
+    pub fn flash(&self, id: &EntryId) -> f32 {
+        self.launches
+            .iter()
+            .find(|launch| launch.id == *id)
+            .map_or(0.0, |launch| {
+                let elapsed = launch.launched_at.elapsed().as_secs_f32();
+                (1.0 - elapsed / FLASH_DURATION.as_secs_f32()).max(0.0)
+            })
+    }
+
+    /// Keep repainting until the newest flash has faded
+    pub fn request_repaint(&self, ctx: &egui::Context) {
+        if let Some(newest) = self.launches.front()
+            && newest.launched_at.elapsed() < FLASH_DURATION
+        {
+            ctx.request_repaint();
+        }
+    }
+
+    /// A row of small buttons for the recent launches, returning the one clicked to launch it
+    /// again
+    pub fn strip(&self, ui: &mut egui::Ui) -> Option<EntryId> {
+        if self.launches.is_empty() {
+            return None;
+        }
+        let mut clicked = None;
+        ui.horizontal_wrapped(|ui| {
+            ui.weak("Recent:");
+            for launch in &self.launches {
+                if ui
+                    .small_button(&launch.label)
+                    .on_hover_text(launch.id.to_string())
+                    .clicked()
+                {
+                    clicked = Some(launch.id.clone());
+                }
+            }
+        });
+        ui.add_space(4.0);
+        clicked
+    }
+}
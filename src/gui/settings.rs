@@ -0,0 +1,383 @@
+use crate::gui::QuickLaunchApp;
+use crate::gui::icon_button::close_button;
+use crate::gui::navigation::NavKey;
+use crate::preferences::{
+    BUTTON_HEIGHT_RANGE, FolderLayout, NUM_COLS_RANGE, SCAN_DEPTH_RANGE, SCAN_ENTRIES_RANGE,
+    SortMode, Theme, USAGE_HALF_LIFE_RANGE,
+};
+use crate::utils::ignore_rules::{IGNORE_FILE_NAME, pattern_error};
+use crate::utils::task_files::TaskRunner;
+use crate::utils::terminal::{KeepOpen, builtin_profiles, is_installed};
+use std::num::NonZeroUsize;
+
+/// Open/closed state of the settings window and the work its edits still owe
+#[derive(Default)]
+pub struct SettingsWindow {
+    pub open: bool,
+    /// Preferences changed since the last save
+    unsaved: bool,
+    /// Scan options changed since the last rescan
+    rescan: bool,
+}
+
+/// What one frame of the settings window changed
+#[derive(Default)]
+struct Changes {
+    any: bool,
+    sort: bool,
+    scan: bool,
+    theme: bool,
+    /// A text field is being typed into
+    typing: bool,
+}
+
+impl QuickLaunchApp {
+    /// Edit every preference. Changes show up straight away; they are saved, and the roots
+    /// rescanned if needed, once the pointer is released and no ignore pattern is being typed, so
+    /// dragging a value doesn't write the config file every frame.
+    ///
+    /// While the window is open it takes all navigation keys; otherwise they are handed back.
+    pub(super) fn settings_window(
+        &mut self,
+        ctx: &egui::Context,
+        nav_keys: Vec<NavKey>,
+    ) -> Vec<NavKey> {
+        if !self.settings.open {
+            return nav_keys;
+        }
+        let mut open = !nav_keys.contains(&NavKey::Close);
+        let mut changes = Changes::default();
+        egui::Window::new("Settings")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("settings_grid")
+                    .num_columns(2)
+                    .spacing([24.0, 8.0])
+                    .show(ui, |ui| self.settings_grid(ui, &mut changes));
+            });
+
+        if changes.theme {
+            ctx.set_theme(self.app_preferences.theme);
+        }
+        if changes.sort {
+            self.sort_entries();
+        }
+        self.settings.unsaved |= changes.any;
+        self.settings.rescan |= changes.scan;
+        let editing = changes.typing || ctx.input(|input| input.pointer.any_down());
+        if self.settings.unsaved && (!editing || !open) {
+            self.settings.unsaved = false;
+            self.save_preferences();
+            if std::mem::take(&mut self.settings.rescan) {
+                self.rescan_roots(ctx);
+            }
+        }
+        self.settings.open = open;
+        Vec::new()
+    }
+
+    fn settings_grid(&mut self, ui: &mut egui::Ui, changes: &mut Changes) {
+        let preferences = &mut self.app_preferences;
+
+        ui.label("Columns");
+        let mut num_cols = preferences.num_cols.get();
+        if ui
+            .add(egui::DragValue::new(&mut num_cols).range(NUM_COLS_RANGE))
+            .changed()
+            && let Some(num_cols) = NonZeroUsize::new(num_cols)
+        {
+            preferences.num_cols = num_cols;
+            changes.any = true;
+        }
+        ui.end_row();
+
+        ui.label("Button height");
+        changes.any |= ui
+            .add(
+                egui::DragValue::new(&mut preferences.button_height)
+                    .range(BUTTON_HEIGHT_RANGE)
+                    .suffix(" pt"),
+            )
+            .changed();
+        ui.end_row();
+
+        ui.label("Theme");
+        let theme = preferences.theme;
+        egui::ComboBox::from_id_salt("settings_theme")
+            .selected_text(theme_label(theme))
+            .show_ui(ui, |ui| {
+                for option in [Theme::System, Theme::Light, Theme::Dark] {
+                    ui.selectable_value(&mut preferences.theme, option, theme_label(option));
+                }
+            });
+        changes.theme = preferences.theme != theme;
+        ui.end_row();
+
+        ui.label("Sort buttons by");
+        let sort_mode = preferences.sort_mode;
+        egui::ComboBox::from_id_salt("settings_sort_mode")
+            .selected_text(sort_mode_label(sort_mode))
+            .show_ui(ui, |ui| {
+                for option in [SortMode::Usage, SortMode::Name] {
+                    ui.selectable_value(
+                        &mut preferences.sort_mode,
+                        option,
+                        sort_mode_label(option),
+                    );
+                }
+            });
+        ui.end_row();
+
+        ui.label("Folders");
+        let folder_layout = preferences.folder_layout;
+        egui::ComboBox::from_id_salt("settings_folder_layout")
+            .selected_text(folder_layout_label(folder_layout))
+            .show_ui(ui, |ui| {
+                for option in [FolderLayout::Flat, FolderLayout::Tree] {
+                    ui.selectable_value(
+                        &mut preferences.folder_layout,
+                        option,
+                        folder_layout_label(option),
+                    );
+                }
+            });
+        changes.any |= preferences.folder_layout != folder_layout;
+        ui.end_row();
+
+        ui.label("Usage half-life");
+        let half_life = preferences.usage_half_life_days;
+        ui.add(
+            egui::DragValue::new(&mut preferences.usage_half_life_days)
+                .range(USAGE_HALF_LIFE_RANGE)
+                .speed(0.5)
+                .suffix(" days"),
+        )
+        .on_hover_text("How quickly old launches stop counting towards the usage order");
+        changes.sort =
+            preferences.sort_mode != sort_mode || preferences.usage_half_life_days != half_life;
+        ui.end_row();
+
+        ui.label("Terminal");
+        let terminal = preferences.terminal.clone();
+        // User profiles shadow built-in ones of the same name
+        let mut names: Vec<String> = Vec::new();
+        for profile in preferences
+            .terminal_profiles
+            .iter()
+            .chain(&builtin_profiles())
+        {
+            if !names.contains(&profile.name) {
+                names.push(profile.name.clone());
+            }
+        }
+        egui::ComboBox::from_id_salt("settings_terminal")
+            .selected_text(terminal.as_deref().unwrap_or("Auto-detect"))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut preferences.terminal, None, "Auto-detect");
+                for name in names {
+                    let label = name.clone();
+                    ui.selectable_value(&mut preferences.terminal, Some(name), label);
+                }
+            });
+        changes.any |= preferences.terminal != terminal;
+        ui.end_row();
+
+        // Only Linux starts scripts through the profiles
+        if cfg!(target_os = "linux") {
+            let (profile, _) = preferences.terminal_for(None);
+            if let Some(program) = profile.program()
+                && !is_installed(program)
+            {
+                ui.label("");
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    format!("{program} is not installed"),
+                );
+                ui.end_row();
+            }
+        }
+
+        ui.label("After launching");
+        changes.any |= ui
+            .checkbox(&mut preferences.stay_open, "Keep the launcher open")
+            .on_hover_text("Shift-click a button to keep it open for a single launch")
+            .changed();
+        ui.end_row();
+
+        ui.label("When a script exits");
+        let keep_open = preferences.keep_open;
+        egui::ComboBox::from_id_salt("settings_keep_open")
+            .selected_text(keep_open_label(keep_open))
+            .show_ui(ui, |ui| {
+                for option in KeepOpen::ALL {
+                    ui.selectable_value(
+                        &mut preferences.keep_open,
+                        option,
+                        keep_open_label(option),
+                    );
+                }
+            });
+        changes.any |= preferences.keep_open != keep_open;
+        ui.end_row();
+
+        ui.label("Hidden files");
+        changes.scan |= ui
+            .checkbox(
+                &mut preferences.scan.include_hidden,
+                "List dot-files and dot-folders",
+            )
+            .changed();
+        ui.end_row();
+
+        ui.label("Scan depth");
+        changes.scan |= ui
+            .add(egui::DragValue::new(&mut preferences.scan.max_depth).range(SCAN_DEPTH_RANGE))
+            .on_hover_text("Folder levels scanned below each root; 0 scans only the root itself")
+            .changed();
+        ui.end_row();
+
+        ui.label("Entry limit");
+        changes.scan |= ui
+            .add(
+                egui::DragValue::new(&mut preferences.scan.max_entries)
+                    .range(SCAN_ENTRIES_RANGE)
+                    .speed(100),
+            )
+            .on_hover_text("Files and folders looked at per root before the scan stops")
+            .changed();
+        ui.end_row();
+
+        ui.label("Symlinks");
+        ui.vertical(|ui| {
+            changes.scan |= ui
+                .checkbox(
+                    &mut preferences.scan.follow_file_symlinks,
+                    "List symlinked scripts",
+                )
+                .changed();
+            changes.scan |= ui
+                .checkbox(
+                    &mut preferences.scan.follow_dir_symlinks,
+                    "Scan symlinked folders",
+                )
+                .on_hover_text("Links back to a folder already scanned are skipped")
+                .changed();
+        });
+        ui.end_row();
+
+        ui.label("Task files");
+        ui.vertical(|ui| {
+            let runners = &mut preferences.scan.task_runners;
+            for runner in TaskRunner::ALL {
+                let mut enabled = runners.contains(&runner);
+                let response = ui
+                    .checkbox(&mut enabled, task_runner_label(runner))
+                    .on_hover_text(runner.file_names().join(", "));
+                if response.changed() {
+                    runners.retain(|&other| other != runner);
+                    if enabled {
+                        runners.push(runner);
+                    }
+                    changes.scan = true;
+                }
+            }
+        });
+        ui.end_row();
+
+        // Desktop files are an XDG convention, which macOS and Windows don't follow
+        if cfg!(all(unix, not(target_os = "macos"))) {
+            ui.label("Applications");
+            changes.scan |= ui
+                .checkbox(
+                    &mut preferences.desktop_apps,
+                    "List installed desktop applications",
+                )
+                .changed();
+            ui.end_row();
+        }
+
+        ui.label("Ignore patterns").on_hover_text(format!(
+            "Gitignore syntax, applied in every root before any {IGNORE_FILE_NAME} file"
+        ));
+        ui.vertical(|ui| {
+            ignore_patterns_editor(ui, &mut preferences.scan.ignore_patterns, changes)
+        });
+        ui.end_row();
+
+        changes.any |= changes.theme || changes.sort || changes.scan;
+    }
+}
+
+/// One text field per pattern with a remove button, plus a button for adding another. Invalid
+/// patterns are flagged; the scan skips them.
+fn ignore_patterns_editor(ui: &mut egui::Ui, patterns: &mut Vec<String>, changes: &mut Changes) {
+    let mut remove = None;
+    for (index, pattern) in patterns.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            let error = pattern_error(pattern);
+            let mut edit = egui::TextEdit::singleline(pattern).desired_width(160.0);
+            if error.is_some() {
+                edit = edit.text_color(ui.visuals().error_fg_color);
+            }
+            let response = ui.add(edit);
+            changes.scan |= response.changed();
+            changes.typing |= response.has_focus();
+            if let Some(error) = error {
+                response.on_hover_text(error);
+            }
+            if close_button(ui).on_hover_text("Remove pattern").clicked() {
+                remove = Some(index);
+            }
+        });
+    }
+    if let Some(index) = remove {
+        patterns.remove(index);
+        changes.scan = true;
+    }
+    if ui.button("Add pattern").clicked() {
+        patterns.push(String::new());
+        changes.any = true;
+    }
+}
+
+fn theme_label(theme: Theme) -> &'static str {
+    match theme {
+        Theme::System => "Follow system",
+        Theme::Light => "Light",
+        Theme::Dark => "Dark",
+    }
+}
+
+fn sort_mode_label(sort_mode: SortMode) -> &'static str {
+    match sort_mode {
+        SortMode::Usage => "Recent usage",
+        SortMode::Name => "Name",
+    }
+}
+
+fn folder_layout_label(folder_layout: FolderLayout) -> &'static str {
+    match folder_layout {
+        FolderLayout::Flat => "One section per top-level folder",
+        FolderLayout::Tree => "Nested, collapsible",
+    }
+}
+
+fn task_runner_label(runner: TaskRunner) -> &'static str {
+    match runner {
+        TaskRunner::CargoMake => "cargo make tasks",
+        TaskRunner::Make => "make targets",
+        TaskRunner::Just => "just recipes",
+        TaskRunner::Npm => "npm scripts",
+    }
+}
+
+fn keep_open_label(keep_open: KeepOpen) -> &'static str {
+    match keep_open {
+        KeepOpen::Close => "Close the terminal",
+        KeepOpen::PauseOnFailure => "Wait for a key if it failed",
+        KeepOpen::Pause => "Wait for a key",
+        KeepOpen::Shell => "Open a shell",
+    }
+}
@@ -0,0 +1,89 @@
+use crate::gui::icon_button::close_button;
+use crate::utils::error::{AppError, AppResult};
+use std::time::{Duration, Instant};
+
+/// Toasts fade away after this long unless the pointer is over them
+const TOAST_LIFETIME: Duration = Duration::from_secs(10);
+const TOAST_WIDTH: f32 = 320.0;
+
+/// A failure shown in the corner of the window
+struct Toast {
+    message: String,
+    details: String,
+    shown_at: Instant,
+}
+
+/// Non-blocking notifications stacked in the bottom-right corner, newest at the bottom
+#[derive(Default)]
+pub struct Toasts {
+    toasts: Vec<Toast>,
+}
+
+impl Toasts {
+    pub fn error(&mut self, error: &AppError) {
+        self.toasts.push(Toast {
+            message: error.to_string(),
+            details: error.details(),
+            shown_at: Instant::now(),
+        });
+    }
+
+    /// Show the error, if any
+    pub fn report(&mut self, result: AppResult<()>) {
+        if let Err(error) = result {
+            self.error(&error);
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        if self.toasts.is_empty() {
+            return;
+        }
+        let mut dismissed = None;
+        let mut hovered = false;
+        egui::Area::new(egui::Id::new("toasts"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-12.0, -12.0))
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                ui.set_max_width(TOAST_WIDTH);
+                for (index, toast) in self.toasts.iter().enumerate() {
+                    let response = egui::Frame::popup(ui.style())
+                        .show(ui, |ui| {
+                            ui.set_width(TOAST_WIDTH);
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
+                                if close_button(ui).on_hover_text("Dismiss").clicked() {
+                                    dismissed = Some(index);
+                                }
+                                let message = egui::RichText::new(&toast.message)
+                                    .color(ui.visuals().error_fg_color);
+                                ui.add(egui::Label::new(message).wrap());
+                            });
+                            if toast.details != toast.message {
+                                ui.collapsing("Details", |ui| ui.monospace(&toast.details));
+                            }
+                            if ui.button("Copy error").clicked() {
+                                ui.ctx().copy_text(toast.details.clone());
+                            }
+                        })
+                        .response;
+                    hovered |= response.contains_pointer();
+                }
+            });
+
+        if let Some(index) = dismissed {
+            self.toasts.remove(index);
+        }
+        // Keep every toast around while the user is reading or copying one of them
+        let now = Instant::now();
+        for toast in &mut self.toasts {
+            if hovered {
+                toast.shown_at = now;
+            }
+        }
+        self.toasts
+            .retain(|toast| now.duration_since(toast.shown_at) < TOAST_LIFETIME);
+        if let Some(oldest) = self.toasts.iter().map(|toast| toast.shown_at).min() {
+            ctx.request_repaint_after(TOAST_LIFETIME.saturating_sub(now.duration_since(oldest)));
+        }
+    }
+}
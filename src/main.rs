@@ -1,10 +1,16 @@
+mod arg_history;
+mod cli;
 mod gui;
 mod preferences;
+mod scan_cache;
 mod usage_stats;
 mod utils;
 
+use crate::cli::Cli;
 use crate::utils::config::APP_TITLE;
+use clap::Parser;
 use eframe::icon_data;
+use std::process::ExitCode;
 
 fn run_gui() -> eframe::Result {
     const ICON_BYTES: &[u8] = include_bytes!("../assets/icons/quick_launch.png");
@@ -35,8 +41,21 @@ fn run_gui() -> eframe::Result {
     )
 }
 
-fn main() {
-    run_gui().expect(
-        "Failed to run the GUI. Please make sure you have the latest version of the GUI installed.",
-    )
+fn main() -> ExitCode {
+    let Some(command) = Cli::parse().command else {
+        run_gui().expect(
+            "Failed to run the GUI. Please make sure you have the latest version of the GUI installed.",
+        );
+        return ExitCode::SUCCESS;
+    };
+    cli::run(command).unwrap_or_else(|err| {
+        let mut message = err.to_string();
+        let mut source = err.source();
+        while let Some(cause) = source {
+            message.push_str(&format!(": {cause}"));
+            source = cause.source();
+        }
+        eprintln!("{}: {message}", utils::config::APP_ID);
+        ExitCode::FAILURE
+    })
 }
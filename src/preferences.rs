@@ -1,49 +1,269 @@
+use crate::utils::build_tree::ScanOptions;
 use crate::utils::config::APP_ID;
+use crate::utils::error::{AppError, AppResult};
+use crate::utils::metadata::ScriptMeta;
+use crate::utils::provider::EntryId;
+use crate::utils::terminal::{KeepOpen, TerminalProfile, select_profile};
 use serde::{Deserialize, Serialize};
 use std::num::NonZeroUsize;
+use std::ops::RangeInclusive;
 use std::path::PathBuf;
 
+/// A directory scanned for executables, shown as its own section
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScriptRoot {
+    pub path: PathBuf,
+    pub label: String,
+    pub enabled: bool,
+    /// RGB accent for the section header
+    pub color: Option<[u8; 3]>,
+}
+
+impl ScriptRoot {
+    /// An enabled root labelled after its folder name
+    pub fn new(path: PathBuf) -> Self {
+        let label = path
+            .file_name()
+            .unwrap_or(path.as_os_str())
+            .to_string_lossy()
+            .into_owned();
+        ScriptRoot {
+            path,
+            label,
+            enabled: true,
+            color: None,
+        }
+    }
+}
+
 /// Store application preferences
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AppPreferences {
-    /// Directory currently in use for detecting executables
+    /// Directory used for detecting executables before multiple roots were supported; moved into
+    /// `roots` on load
+    #[serde(default)]
     pub script_dir: Option<PathBuf>,
     /// Our default directory
     pub default_script_dir: PathBuf,
     /// Number of columns in the UI grid
     pub num_cols: NonZeroUsize,
+    /// Height of a launcher button in points
+    #[serde(default = "default_button_height")]
+    pub button_height: f32,
+    #[serde(default)]
+    pub theme: Theme,
+    #[serde(default)]
+    pub sort_mode: SortMode,
+    #[serde(default)]
+    pub folder_layout: FolderLayout,
+    /// Folders whose section is collapsed in the nested layout
+    #[serde(default)]
+    pub collapsed_folders: Vec<PathBuf>,
+    /// How the roots are walked for executables
+    #[serde(default)]
+    pub scan: ScanOptions,
+    /// Directories scanned for executables, in display order
+    #[serde(default)]
+    pub roots: Vec<ScriptRoot>,
+    /// Pinned entries shown above every root, in display order
+    #[serde(default)]
+    pub favorites: Vec<EntryId>,
+    /// Entries left out of the launcher grid
+    #[serde(default)]
+    pub hidden: Vec<EntryId>,
+    /// List the desktop's installed applications in a section of their own
+    #[serde(default)]
+    pub desktop_apps: bool,
+    /// Name of the terminal profile to launch scripts in; detected when unset
+    #[serde(default)]
+    pub terminal: Option<String>,
+    /// User-defined terminal profiles, taking precedence over built-in ones of the same name
+    #[serde(default)]
+    pub terminal_profiles: Vec<TerminalProfile>,
+    /// What a script's terminal does once the script exits
+    #[serde(default)]
+    pub keep_open: KeepOpen,
+    /// Keep the launcher open after starting a script in a terminal
+    #[serde(default)]
+    pub stay_open: bool,
+    /// Days after which a launch counts half as much when ranking scripts
+    #[serde(default = "default_usage_half_life_days")]
+    pub usage_half_life_days: f64,
 }
 
-fn get_default_script_dir() -> PathBuf {
-    dirs::executable_dir().unwrap().join(APP_ID)
+/// Light or dark visuals
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// Follow the desktop
+    #[default]
+    System,
+    Light,
+    Dark,
 }
 
-impl AppPreferences {
-    pub fn new(script_dir: Option<PathBuf>, num_cols: NonZeroUsize) -> Self {
-        AppPreferences {
-            script_dir,
-            default_script_dir: get_default_script_dir(),
-            num_cols,
+impl From<Theme> for egui::ThemePreference {
+    fn from(theme: Theme) -> Self {
+        match theme {
+            Theme::System => egui::ThemePreference::System,
+            Theme::Light => egui::ThemePreference::Light,
+            Theme::Dark => egui::ThemePreference::Dark,
         }
     }
+}
+
+/// Order of the buttons within each section
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SortMode {
+    /// Most launched recently first
+    #[default]
+    Usage,
+    /// Alphabetical by label
+    Name,
+}
+
+/// How subfolders of a root are laid out
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum FolderLayout {
+    /// One section per top-level folder, listing everything below it
+    #[default]
+    Flat,
+    /// Collapsible sections nested like the folders on disk
+    Tree,
+}
 
+/// Values the settings window accepts; anything else in the config file is clamped on load
+pub const NUM_COLS_RANGE: RangeInclusive<usize> = 1..=12;
+pub const BUTTON_HEIGHT_RANGE: RangeInclusive<f32> = 20.0..=96.0;
+pub const USAGE_HALF_LIFE_RANGE: RangeInclusive<f64> = 0.5..=365.0;
+pub const SCAN_DEPTH_RANGE: RangeInclusive<usize> = 0..=32;
+pub const SCAN_ENTRIES_RANGE: RangeInclusive<usize> = 100..=1_000_000;
+
+fn default_button_height() -> f32 {
+    32.0
+}
+
+fn default_usage_half_life_days() -> f64 {
+    14.0
+}
+
+/// `value` clamped into `range`, or `default` if it is not comparable (NaN)
+fn clamp_or_default<T: PartialOrd + Copy>(value: T, range: &RangeInclusive<T>, default: T) -> T {
+    if range.contains(&value) {
+        value
+    } else if value < *range.start() {
+        *range.start()
+    } else if value > *range.end() {
+        *range.end()
+    } else {
+        default
+    }
+}
+
+/// `~/.local/bin/quick_launch` on Linux; platforms without a per-user executable dir use the
+/// local data dir instead, and the home dir when neither is known
+fn get_default_script_dir() -> PathBuf {
+    dirs::executable_dir()
+        .or_else(dirs::data_local_dir)
+        .or_else(dirs::home_dir)
+        .unwrap_or_else(std::env::temp_dir)
+        .join(APP_ID)
+}
+
+impl AppPreferences {
     pub fn default() -> Self {
         let default_script_dir = get_default_script_dir();
         let default_script_copy = default_script_dir.clone();
 
         AppPreferences {
-            script_dir: Some(default_script_dir),
+            script_dir: None,
             default_script_dir: default_script_copy,
             num_cols: NonZeroUsize::new(3).expect("Default number of columns must be non-zero"),
+            button_height: default_button_height(),
+            theme: Theme::default(),
+            sort_mode: SortMode::default(),
+            folder_layout: FolderLayout::default(),
+            collapsed_folders: Vec::new(),
+            scan: ScanOptions::default(),
+            roots: vec![ScriptRoot::new(default_script_dir)],
+            favorites: Vec::new(),
+            hidden: Vec::new(),
+            desktop_apps: false,
+            terminal: None,
+            terminal_profiles: Vec::new(),
+            keep_open: KeepOpen::default(),
+            stay_open: false,
+            usage_half_life_days: default_usage_half_life_days(),
         }
     }
 
-    pub fn save(&self) {
-        confy::store(APP_ID, None, self).expect("Failed to save preferences")
+    pub fn save(&self) -> AppResult<()> {
+        confy::store(APP_ID, None, self).map_err(|source| AppError::SaveConfig {
+            what: "preferences",
+            source,
+        })
     }
 
     pub fn load() -> Self {
-        confy::load(APP_ID, None).unwrap_or_else(|_| AppPreferences::default())
+        let mut preferences: Self =
+            confy::load(APP_ID, None).unwrap_or_else(|_| AppPreferences::default());
+        preferences.migrate_script_dir();
+        preferences.clamp_to_ranges();
+        preferences
+    }
+
+    /// Pull hand-edited values back into the ranges the settings window offers
+    fn clamp_to_ranges(&mut self) {
+        let num_cols = self
+            .num_cols
+            .get()
+            .clamp(*NUM_COLS_RANGE.start(), *NUM_COLS_RANGE.end());
+        self.num_cols = NonZeroUsize::new(num_cols).unwrap_or(self.num_cols);
+        self.button_height = clamp_or_default(
+            self.button_height,
+            &BUTTON_HEIGHT_RANGE,
+            default_button_height(),
+        );
+        self.usage_half_life_days = clamp_or_default(
+            self.usage_half_life_days,
+            &USAGE_HALF_LIFE_RANGE,
+            default_usage_half_life_days(),
+        );
+        self.scan.max_depth = self.scan.max_depth.min(*SCAN_DEPTH_RANGE.end());
+        self.scan.max_entries = self
+            .scan
+            .max_entries
+            .clamp(*SCAN_ENTRIES_RANGE.start(), *SCAN_ENTRIES_RANGE.end());
+    }
+
+    /// Turn a single-directory config into the first entry of `roots`
+    fn migrate_script_dir(&mut self) {
+        let legacy_dir = self.script_dir.take();
+        if self.roots.is_empty() {
+            let path = legacy_dir.unwrap_or_else(|| self.default_script_dir.clone());
+            self.roots.push(ScriptRoot::new(path));
+        }
+    }
+
+    /// Terminal profile and keep-open policy for a script, honouring its `ql:terminal` and
+    /// `ql:keep_open` overrides
+    pub fn terminal_for(&self, meta: Option<&ScriptMeta>) -> (TerminalProfile, KeepOpen) {
+        let profile_name = meta
+            .and_then(|meta| meta.terminal.as_deref())
+            .or(self.terminal.as_deref());
+        let keep_open = meta
+            .and_then(|meta| meta.keep_open)
+            .unwrap_or(self.keep_open);
+        (
+            select_profile(&self.terminal_profiles, profile_name),
+            keep_open,
+        )
+    }
+
+    pub fn enabled_roots(&self) -> impl Iterator<Item = &ScriptRoot> {
+        self.roots.iter().filter(|root| root.enabled)
     }
 }
 
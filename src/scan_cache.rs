@@ -0,0 +1,77 @@
+use crate::preferences::ScriptRoot;
+use crate::utils::build_tree::{RootFolder, ScanOptions};
+use crate::utils::config::APP_ID;
+use crate::utils::error::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+const CONFIG_NAME: &str = "scan_cache";
+
+/// The tree found by the last complete scan, so the next start can show it straight away and only
+/// re-list folders modified since. Stored as JSON next to the config files, as it can get large.
+#[derive(Serialize, Deserialize, Default)]
+pub struct ScanCache {
+    /// Options the tree was scanned with; a tree scanned differently can't be reused
+    options: ScanOptions,
+    roots: Vec<RootFolder>,
+}
+
+impl ScanCache {
+    pub fn new(options: &ScanOptions, roots: &[RootFolder]) -> Self {
+        ScanCache {
+            options: options.clone(),
+            roots: roots.to_vec(),
+        }
+    }
+
+    /// The last saved cache, or an empty one if there is none or it can't be read
+    pub fn load() -> Self {
+        cache_path()
+            .and_then(|path| std::fs::read(path).ok())
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> AppResult<()> {
+        let Some(path) = cache_path() else {
+            return Ok(());
+        };
+        let write = || {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let json = serde_json::to_vec(self).map_err(std::io::Error::other)?;
+            std::fs::write(&path, json)
+        };
+        write().map_err(|source| AppError::WriteFile {
+            path: path.clone(),
+            source,
+        })
+    }
+
+    /// The cached tree of `root` if it was scanned with `options` and scanned completely, with
+    /// the label and color brought up to date
+    pub fn take_root(&mut self, root: &ScriptRoot, options: &ScanOptions) -> Option<RootFolder> {
+        if self.options != *options {
+            return None;
+        }
+        let index = self
+            .roots
+            .iter()
+            .position(|cached| cached.path == root.path)?;
+        let mut cached = self.roots.swap_remove(index);
+        if cached.is_truncated() {
+            return None;
+        }
+        cached.label = root.label.clone();
+        cached.color = root.color;
+        cached.restore_flat_entries();
+        Some(cached)
+    }
+}
+
+fn cache_path() -> Option<PathBuf> {
+    confy::get_configuration_file_path(APP_ID, Some(CONFIG_NAME))
+        .ok()
+        .map(|path| path.with_extension("json"))
+}
@@ -1,38 +1,216 @@
 use crate::utils::config::APP_ID;
+use crate::utils::error::{AppError, AppResult};
+use crate::utils::provider::EntryId;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 const CONFIG_NAME: &str = "usage_stats";
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+/// Past this many days of history the two oldest buckets are merged, so the file stays small for
+/// scripts launched every day
+const MAX_BUCKETS: usize = 32;
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct UsageStats {
-    pub counts: HashMap<String, u64>,
+    #[serde(default)]
+    pub history: HashMap<EntryId, LaunchHistory>,
+    /// Plain launch counts written by older versions; folded into `history` on load
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    counts: HashMap<String, u64>,
+}
+
+/// Launches of one script as `(day, launches)` buckets, oldest first, with days counted from the
+/// Unix epoch
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(transparent)]
+pub struct LaunchHistory {
+    buckets: Vec<(u32, u32)>,
+}
+
+impl LaunchHistory {
+    fn record(&mut self, day: u32, launches: u32) {
+        match self.buckets.last_mut() {
+            Some((last_day, count)) if *last_day >= day => *count = count.saturating_add(launches),
+            _ => self.buckets.push((day, launches)),
+        }
+        if self.buckets.len() > MAX_BUCKETS {
+            // Keep the older day so merging never makes old launches look more recent
+            let (_, newer) = self.buckets.remove(1);
+            self.buckets[0].1 = self.buckets[0].1.saturating_add(newer);
+        }
+    }
+
+    pub fn launches(&self) -> u64 {
+        self.buckets
+            .iter()
+            .map(|&(_, count)| u64::from(count))
+            .sum()
+    }
+
+    /// Launch count where each launch loses half its weight every `half_life_days`
+    pub fn frecency(&self, today: u32, half_life_days: f64) -> f64 {
+        let half_life_days = half_life_days.max(f64::EPSILON);
+        self.buckets
+            .iter()
+            .map(|&(day, count)| {
+                let age = f64::from(today.saturating_sub(day));
+                f64::from(count) * (-age / half_life_days).exp2()
+            })
+            .sum()
+    }
 }
 
 impl UsageStats {
-    pub fn load() -> Self {
-        confy::load(APP_ID, Some(CONFIG_NAME)).unwrap_or_default()
+    /// Load the stats, migrating counts written by older versions. The result is that of saving
+    /// the migrated stats; a failed save loses nothing, as the next save writes them again.
+    pub fn load() -> (Self, AppResult<()>) {
+        let mut stats: Self = confy::load(APP_ID, Some(CONFIG_NAME)).unwrap_or_default();
+        let result = if stats.counts.is_empty() {
+            Ok(())
+        } else {
+            stats.migrate_counts()
+        };
+        (stats, result)
+    }
+
+    pub fn save(&self) -> AppResult<()> {
+        confy::store(APP_ID, Some(CONFIG_NAME), self).map_err(|source| AppError::SaveConfig {
+            what: "usage stats",
+            source,
+        })
+    }
+
+    fn migrate_counts(&mut self) -> AppResult<()> {
+        self.fold_counts(today());
+        self.save()
+    }
+
+    /// Count-only stats carry no dates, so their launches are treated as happening on `today`
+    /// and fade out from there
+    fn fold_counts(&mut self, today: u32) {
+        for (key, count) in std::mem::take(&mut self.counts) {
+            let launches = u32::try_from(count).unwrap_or(u32::MAX);
+            self.history
+                .entry(EntryId::from(key))
+                .or_default()
+                .record(today, launches);
+        }
+    }
+
+    pub fn increment(&mut self, id: &EntryId) -> AppResult<()> {
+        self.history
+            .entry(id.clone())
+            .or_default()
+            .record(today(), 1);
+        self.save()
     }
 
-    pub fn save(&self) {
-        confy::store(APP_ID, Some(CONFIG_NAME), self).expect("Failed to save usage stats")
+    pub fn frecency(&self, id: &EntryId, half_life_days: f64) -> f64 {
+        self.history
+            .get(id)
+            .map_or(0.0, |history| history.frecency(today(), half_life_days))
     }
 
-    pub fn increment(&mut self, path: &Path) {
-        let key = path.to_string_lossy().into_owned();
-        *self.counts.entry(key).or_insert(0) += 1;
-        self.save();
+    /// Forget every launch of `id`
+    pub fn reset(&mut self, id: &EntryId) -> AppResult<()> {
+        self.history.remove(id);
+        self.save()
     }
 
-    pub fn get(&self, path: &Path) -> u64 {
-        let key = path.to_string_lossy();
-        self.counts.get(key.as_ref()).copied().unwrap_or(0)
+    /// Drop history for the entries `keep` says no to
+    pub fn prune(&mut self, keep: impl Fn(&EntryId) -> bool) -> AppResult<()> {
+        self.history.retain(|id, _| keep(id));
+        self.save()
     }
+}
+
+/// Days since the Unix epoch
+pub fn today() -> u32 {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    u32::try_from(seconds / SECONDS_PER_DAY).unwrap_or(u32::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    const DAY: u32 = 20_000;
+
+    fn history(buckets: &[(u32, u32)]) -> LaunchHistory {
+        LaunchHistory {
+            buckets: buckets.to_vec(),
+        }
+    }
+
+    #[test]
+    fn launches_on_the_same_day_share_a_bucket() {
+        let mut history = LaunchHistory::default();
+        history.record(DAY, 1);
+        history.record(DAY, 2);
+        history.record(DAY + 1, 1);
+        assert_eq!(history.buckets, [(DAY, 3), (DAY + 1, 1)]);
+        // A clock set back doesn't reorder the buckets
+        history.record(DAY - 5, 1);
+        assert_eq!(history.buckets, [(DAY, 3), (DAY + 1, 2)]);
+        assert_eq!(history.launches(), 5);
+    }
+
+    #[test]
+    fn full_history_merges_its_two_oldest_buckets() {
+        let mut history = LaunchHistory::default();
+        for offset in 0..MAX_BUCKETS as u32 {
+            history.record(DAY + offset, 1);
+        }
+        assert_eq!(history.buckets.len(), MAX_BUCKETS);
+
+        history.record(DAY + 100, 5);
+        assert_eq!(history.buckets.len(), MAX_BUCKETS);
+        // Merged into the older day, so old launches never look more recent
+        assert_eq!(history.buckets[0], (DAY, 2));
+        assert_eq!(history.buckets[1], (DAY + 2, 1));
+        assert_eq!(history.buckets.last(), Some(&(DAY + 100, 5)));
+        assert_eq!(history.launches(), MAX_BUCKETS as u64 + 5);
+    }
+
+    #[test]
+    fn frecency_halves_every_half_life() {
+        let history = history(&[(DAY - 28, 8), (DAY - 14, 4), (DAY, 2)]);
+        assert_eq!(history.frecency(DAY, 14.0), 2.0 + 2.0 + 2.0);
+        assert_eq!(history.frecency(DAY + 14, 14.0), 1.0 + 1.0 + 1.0);
+        // Launches dated after today count in full
+        assert_eq!(history.frecency(DAY - 28, 14.0), 8.0 + 4.0 + 2.0);
+        assert_eq!(LaunchHistory::default().frecency(DAY, 14.0), 0.0);
+    }
+
+    #[test]
+    fn zero_half_life_only_counts_today() {
+        let history = history(&[(DAY - 1, 10), (DAY, 3)]);
+        assert_eq!(history.frecency(DAY, 0.0), 3.0);
+    }
+
+    #[test]
+    fn legacy_counts_become_launches_today() {
+        let mut stats = UsageStats {
+            counts: HashMap::from([
+                ("/home/me/bin/old.sh".to_owned(), 7),
+                ("make:/home/me/Makefile#build".to_owned(), 2),
+                ("/home/me/bin/big.sh".to_owned(), u64::MAX),
+            ]),
+            ..UsageStats::default()
+        };
+        let make = EntryId::from("make:/home/me/Makefile#build".to_owned());
+        stats.history.insert(make.clone(), history(&[(DAY - 3, 1)]));
 
-    pub fn prune(&mut self, root: &Path) {
-        self.counts
-            .retain(|key, _| Path::new(key).starts_with(root));
-        self.save();
+        stats.fold_counts(DAY);
+        assert!(stats.counts.is_empty());
+        let old = &stats.history[&EntryId::script(Path::new("/home/me/bin/old.sh"))];
+        assert_eq!(old.buckets, [(DAY, 7)]);
+        assert_eq!(stats.history[&make].buckets, [(DAY - 3, 1), (DAY, 2)]);
+        let big = &stats.history[&EntryId::script(Path::new("/home/me/bin/big.sh"))];
+        assert_eq!(big.buckets, [(DAY, u32::MAX)]);
     }
 }
@@ -0,0 +1,139 @@
+use super::launch::CommandSpec;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+/// Oldest lines are dropped past this so a chatty script can't eat all memory
+const MAX_OUTPUT_LINES: usize = 5_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RunStatus {
+    Running,
+    /// Exited with status 0
+    Finished,
+    /// Exited non-zero (`None` when killed by a signal) or could not be waited on
+    Failed(Option<i32>),
+}
+
+enum RunEvent {
+    Line(Stream, String),
+    Exited(Option<i32>),
+}
+
+/// A script started without a terminal, with its output collected as it arrives
+pub struct BackgroundRun {
+    pub label: String,
+    pub path: PathBuf,
+    pub output: Vec<(Stream, String)>,
+    pub status: RunStatus,
+    rx: Receiver<RunEvent>,
+}
+
+impl BackgroundRun {
+    /// Pull whatever output arrived since the last call
+    pub fn poll(&mut self) {
+        while let Ok(event) = self.rx.try_recv() {
+            match event {
+                RunEvent::Line(stream, line) => self.output.push((stream, line)),
+                RunEvent::Exited(Some(0)) => self.status = RunStatus::Finished,
+                RunEvent::Exited(code) => self.status = RunStatus::Failed(code),
+            }
+        }
+        if self.output.len() > MAX_OUTPUT_LINES {
+            let excess = self.output.len() - MAX_OUTPUT_LINES;
+            self.output.drain(..excess);
+        }
+    }
+
+    /// A run that never got going, so the error still shows up in the output panel
+    pub fn failed_to_start(script_path: &Path, label: String, error: &io::Error) -> Self {
+        let (_, rx) = mpsc::channel();
+        BackgroundRun {
+            label,
+            path: script_path.to_path_buf(),
+            output: vec![(Stream::Stderr, format!("Failed to start: {error}"))],
+            status: RunStatus::Failed(None),
+            rx,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.status == RunStatus::Running
+    }
+}
+
+/// Start `command` for the entry at `path` directly, piping stdout and stderr back to the
+/// launcher.
+pub fn spawn_background(
+    path: &Path,
+    command: &CommandSpec,
+    label: String,
+) -> io::Result<BackgroundRun> {
+    let mut child = Command::new(&command.program)
+        .args(&command.args)
+        .current_dir(&command.cwd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let (tx, rx) = mpsc::channel();
+    let readers = [
+        child
+            .stdout
+            .take()
+            .map(|out| forward_lines(out, Stream::Stdout, tx.clone())),
+        child
+            .stderr
+            .take()
+            .map(|err| forward_lines(err, Stream::Stderr, tx.clone())),
+    ];
+    thread::spawn(move || {
+        // Drain both pipes before reporting the exit so no output lands after the badge changes
+        for reader in readers.into_iter().flatten() {
+            let _ = reader.join();
+        }
+        let code = child.wait().ok().and_then(|status| status.code());
+        // ignore send error (UI might have dropped the run)
+        let _ = tx.send(RunEvent::Exited(code));
+    });
+
+    Ok(BackgroundRun {
+        label,
+        path: path.to_path_buf(),
+        output: Vec::new(),
+        status: RunStatus::Running,
+        rx,
+    })
+}
+
+fn forward_lines(
+    pipe: impl Read + Send + 'static,
+    stream: Stream,
+    tx: Sender<RunEvent>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(pipe);
+        let mut buf = Vec::new();
+        // Keep draining even if nobody listens any more, or the child would block on a full pipe
+        while let Ok(read) = reader.read_until(b'\n', &mut buf) {
+            if read == 0 {
+                break;
+            }
+            let line = String::from_utf8_lossy(&buf);
+            let _ = tx.send(RunEvent::Line(
+                stream,
+                line.trim_end_matches(['\n', '\r']).to_owned(),
+            ));
+            buf.clear();
+        }
+    })
+}
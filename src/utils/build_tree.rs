@@ -1,82 +1,882 @@
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::SystemTime;
 
-use super::file_ops::is_executable;
+use super::file_ops::modified;
+use super::ignore_rules::{IGNORE_FILE_NAME, IgnoreRules, default_ignore_patterns};
+use super::launch::CommandSpec;
+use super::metadata::ScriptMeta;
+use super::provider::{EntryId, Provider, file_providers, provider};
+use super::task_files::TaskRunner;
+use crate::preferences::ScriptRoot;
+use serde::{Deserialize, Serialize};
 
+/// Folder levels scanned below each root unless configured otherwise
+const DEFAULT_MAX_DEPTH: usize = 8;
+/// Directory entries looked at per root before the scan stops, unless configured otherwise
+const DEFAULT_MAX_ENTRIES: usize = 10_000;
+
+/// How script roots are walked
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct ScanOptions {
+    /// Also list dot-files and look inside dot-folders
+    pub include_hidden: bool,
+    /// Folder levels below each root that are scanned; 0 scans only the root itself
+    pub max_depth: usize,
+    /// Directory entries looked at per root before the scan stops
+    pub max_entries: usize,
+    /// Descend into symlinked folders; loops are detected either way
+    pub follow_dir_symlinks: bool,
+    /// List symlinked executables
+    pub follow_file_symlinks: bool,
+    /// Gitignore-style patterns applied in every root, before any `.quicklaunchignore`
+    pub ignore_patterns: Vec<String>,
+    /// Tools whose task files are read, listing each task next to the scripts
+    pub task_runners: Vec<TaskRunner>,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        ScanOptions {
+            include_hidden: false,
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_entries: DEFAULT_MAX_ENTRIES,
+            follow_dir_symlinks: false,
+            follow_file_symlinks: true,
+            ignore_patterns: default_ignore_patterns(),
+            task_runners: TaskRunner::ALL.to_vec(),
+        }
+    }
+}
+
+/// A limit the scan ran into, leaving the tree incomplete at `path`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum ScanWarning {
+    /// This folder is at `max_depth`; its subfolders were left out
+    TooDeep { path: PathBuf },
+    /// A folder leads back to one already scanned, e.g. a symlink to one of its parents
+    Loop { path: PathBuf },
+    /// The scan stopped in this folder after looking at `max_entries` entries
+    TooManyEntries { path: PathBuf },
+}
+
+impl ScanWarning {
+    pub fn path(&self) -> &Path {
+        match self {
+            ScanWarning::TooDeep { path }
+            | ScanWarning::Loop { path }
+            | ScanWarning::TooManyEntries { path } => path,
+        }
+    }
+
+    /// The folder whose listing produced the warning, so it still holds while that folder is
+    /// unchanged
+    fn dir(&self) -> &Path {
+        match self {
+            ScanWarning::Loop { path } => path.parent().unwrap_or(path),
+            ScanWarning::TooDeep { path } | ScanWarning::TooManyEntries { path } => path,
+        }
+    }
+}
+
+impl std::fmt::Display for ScanWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScanWarning::TooDeep { path } => {
+                write!(
+                    f,
+                    "Subfolders of {} are below the depth limit",
+                    path.display()
+                )
+            }
+            ScanWarning::Loop { path } => {
+                write!(f, "{} leads to a folder already scanned", path.display())
+            }
+            ScanWarning::TooManyEntries { path } => write!(
+                f,
+                "Scan stopped in {} after reaching the entry limit",
+                path.display()
+            ),
+        }
+    }
+}
+
+/// One configured script root, scanned
+#[derive(Serialize, Deserialize, Clone)]
 pub struct RootFolder {
+    pub label: String,
+    pub path: PathBuf,
+    /// RGB accent for the section header
+    pub color: Option<[u8; 3]>,
+    /// Modification time of the root when its entries were read; see [`listing_mtime`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mtime: Option<SystemTime>,
     pub entries: Vec<Entry>,
+    /// Names of the files directly in the root that no provider made entries of
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub other_files: Vec<String>,
     pub folders: Vec<Folder>,
+    /// Limits hit during the scan; empty if the whole root was read
+    pub warnings: Vec<ScanWarning>,
 }
 
 impl RootFolder {
-    pub fn sort_by_usage(&mut self, score: &dyn Fn(&Path) -> u64) {
+    /// The root before any of it has been read
+    pub fn unscanned(root: &ScriptRoot) -> Self {
+        RootFolder {
+            label: root.label.clone(),
+            path: root.path.clone(),
+            color: root.color,
+            mtime: None,
+            entries: Vec::new(),
+            other_files: Vec::new(),
+            folders: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Number of scripts found anywhere below the root
+    pub fn entry_count(&self) -> usize {
+        self.entries.len()
+            + self
+                .folders
+                .iter()
+                .map(|folder| folder.flat_entries.len())
+                .sum::<usize>()
+    }
+
+    /// Whether the scan stopped at the entry limit, leaving part of the root unread
+    pub fn is_truncated(&self) -> bool {
+        self.warnings
+            .iter()
+            .any(|warning| matches!(warning, ScanWarning::TooManyEntries { .. }))
+    }
+
+    pub fn sort_by_name(&mut self) {
         self.entries
-            .sort_by(|a, b| score(&b.executable_path).cmp(&score(&a.executable_path)));
+            .sort_by_cached_key(|entry| entry.label().to_lowercase());
         for folder in &mut self.folders {
-            folder
-                .flat_entries
-                .sort_by(|a, b| score(&b.executable_path).cmp(&score(&a.executable_path)));
+            folder.sort_by_name();
+        }
+    }
+
+    pub fn sort_by_usage(&mut self, score: &dyn Fn(&EntryId) -> f64) {
+        self.entries
+            .sort_by(|a, b| score(&b.id).total_cmp(&score(&a.id)));
+        for folder in &mut self.folders {
+            folder.sort_by_usage(score);
+        }
+    }
+
+    /// Paths of every folder at every level below the root
+    pub fn folder_paths(&self) -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+        collect_folder_paths(&self.path, &self.folders, &mut paths);
+        paths
+    }
+
+    /// Swap in the parts of the tree that were re-read; new folders are added in name order.
+    pub fn apply(&mut self, update: TreeUpdate) {
+        // Warnings about the re-read parts are replaced by the ones from the update
+        let root_reread = update.root_entries.is_some();
+        self.warnings.retain(|warning| {
+            let path = warning.path();
+            let in_reread_folder = update
+                .folders
+                .iter()
+                .any(|(name, _)| path.starts_with(self.path.join(name)));
+            let in_reread_root = root_reread && path == self.path;
+            !(in_reread_folder || in_reread_root)
+        });
+        self.warnings.extend(update.warnings);
+
+        if let Some(entries) = update.root_entries {
+            self.entries = entries;
+            self.other_files = update.root_other_files;
+            self.mtime = update.root_mtime;
         }
+        for (name, folder) in update.folders {
+            let existing = self.folders.iter().position(|folder| folder.name == name);
+            match (existing, folder) {
+                (Some(index), Some(folder)) => self.folders[index] = folder,
+                (Some(index), None) => {
+                    self.folders.remove(index);
+                }
+                (None, Some(folder)) => {
+                    // Keep the folders in the order a full scan puts them in
+                    let key = folder.name.to_lowercase();
+                    let index = self
+                        .folders
+                        .partition_point(|existing| existing.name.to_lowercase() < key);
+                    self.folders.insert(index, folder);
+                }
+                (None, None) => {}
+            }
+        }
+    }
+
+    /// Rebuild the derived `flat_entries` of every folder, e.g. after loading the tree from disk
+    pub fn restore_flat_entries(&mut self) {
+        restore_flat_entries(&mut self.folders);
+    }
+
+    /// The entry called `id`, wherever it sits in the tree
+    pub fn find_entry(&self, id: &EntryId) -> Option<&Entry> {
+        find_entry_in(&self.entries, &self.folders, id)
     }
 }
 
+#[derive(Serialize, Clone)]
 pub struct FlatEntry {
     pub display_name: String,
-    pub executable_path: PathBuf,
+    /// File name, or the task name for a task
+    pub name: String,
+    pub id: EntryId,
+    pub path: PathBuf,
+    pub meta: ScriptMeta,
 }
 
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Folder {
     pub name: String,
+    /// Modification time of the folder when it was listed; see [`listing_mtime`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mtime: Option<SystemTime>,
     pub entries: Vec<Entry>,
+    /// Names of the files in the folder that no provider made entries of
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub other_files: Vec<String>,
     pub folders: Vec<Folder>,
+    /// Derived from `entries` and `folders`, so left out of the serialized tree
+    #[serde(skip)]
     pub flat_entries: Vec<FlatEntry>,
 }
 
+impl Folder {
+    fn sort_by_name(&mut self) {
+        self.entries
+            .sort_by_cached_key(|entry| entry.label().to_lowercase());
+        self.flat_entries
+            .sort_by_cached_key(|entry| entry.display_name.to_lowercase());
+        for folder in &mut self.folders {
+            folder.sort_by_name();
+        }
+    }
+
+    fn sort_by_usage(&mut self, score: &dyn Fn(&EntryId) -> f64) {
+        self.entries
+            .sort_by(|a, b| score(&b.id).total_cmp(&score(&a.id)));
+        self.flat_entries
+            .sort_by(|a, b| score(&b.id).total_cmp(&score(&a.id)));
+        for folder in &mut self.folders {
+            folder.sort_by_usage(score);
+        }
+    }
+}
+
+fn restore_flat_entries(folders: &mut [Folder]) {
+    for folder in folders {
+        restore_flat_entries(&mut folder.folders);
+        folder.flat_entries.clear();
+        flatten_entries(
+            &folder.entries,
+            &folder.folders,
+            PathBuf::new(),
+            &mut folder.flat_entries,
+        );
+    }
+}
+
+fn find_entry_in<'a>(
+    entries: &'a [Entry],
+    folders: &'a [Folder],
+    id: &EntryId,
+) -> Option<&'a Entry> {
+    entries.iter().find(|entry| entry.id == *id).or_else(|| {
+        folders
+            .iter()
+            .find_map(|folder| find_entry_in(&folder.entries, &folder.folders, id))
+    })
+}
+
+fn collect_folder_paths(parent: &Path, folders: &[Folder], paths: &mut Vec<PathBuf>) {
+    for folder in folders {
+        let path = parent.join(&folder.name);
+        collect_folder_paths(&path, &folder.folders, paths);
+        paths.push(path);
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Entry {
+    /// What the usage stats, favorites and the like know the entry by
+    pub id: EntryId,
+    /// File name, or the task name for a task
     pub name: String,
-    pub executable_path: PathBuf,
+    /// The file the entry was read from: the script itself, or the task file defining the task
+    pub path: PathBuf,
+    pub meta: ScriptMeta,
+    /// Modification time of the script or task file when it was read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mtime: Option<SystemTime>,
+}
+
+impl Entry {
+    /// Friendly name from the script header, falling back to the file name
+    pub fn label(&self) -> &str {
+        self.meta.name.as_deref().unwrap_or(&self.name)
+    }
+
+    /// The command launching the entry with `args` passed on to it, as its provider builds it
+    pub fn command(&self, args: &[String]) -> CommandSpec {
+        match provider(self.id.provider()) {
+            Some(provider) => provider.command(self, args),
+            None => CommandSpec::script(&self.path, args),
+        }
+    }
+
+    /// Whether the file the entry was read from is unchanged since
+    pub fn file_unchanged(&self) -> bool {
+        let mtime = modified(&self.path);
+        mtime.is_some() && mtime == self.mtime
+    }
+
+    /// Whether the entry can be kept from an earlier scan, as its provider judges it
+    fn is_current(&self) -> bool {
+        match provider(self.id.provider()) {
+            Some(provider) => provider.is_current(self),
+            None => self.file_unchanged(),
+        }
+    }
 }
 
-pub fn build_tree(root_path: &Path) -> RootFolder {
-    let (entries, folders) = read_children(root_path);
-    RootFolder { entries, folders }
+/// Scan every root into its own section, keeping the configured order
+pub fn build_tree<'a>(
+    roots: impl IntoIterator<Item = &'a ScriptRoot>,
+    options: &ScanOptions,
+) -> Vec<RootFolder> {
+    roots
+        .into_iter()
+        .map(|root| build_root(root, options))
+        .collect()
 }
 
-fn read_children(dir: &Path) -> (Vec<Entry>, Vec<Folder>) {
-    let dir_entries = match std::fs::read_dir(dir) {
-        Ok(entries) => entries,
-        Err(_) => return (Vec::new(), Vec::new()),
+pub fn build_root(root: &ScriptRoot, options: &ScanOptions) -> RootFolder {
+    let mut root_folder = RootFolder::unscanned(root);
+    scan_root(
+        root,
+        options,
+        None,
+        &AtomicBool::new(false),
+        &mut |update| {
+            root_folder.apply(update);
+        },
+    );
+    root_folder
+}
+
+/// Scan `root` piece by piece, handing each piece to `report` as soon as it is read: first the
+/// executables directly in the root, then one top-level folder at a time. Gives up, leaving the
+/// rest unreported, once `cancel` is set.
+///
+/// With `cached`, an earlier scan of the root made with the same options, folders whose
+/// modification time hasn't changed are not listed again; only their subfolders are checked.
+pub fn scan_root(
+    root: &ScriptRoot,
+    options: &ScanOptions,
+    cached: Option<&RootFolder>,
+    cancel: &AtomicBool,
+    report: &mut dyn FnMut(TreeUpdate),
+) {
+    let rules = IgnoreRules::for_root(&root.path, &options.ignore_patterns);
+    let cached_warnings = cached.map_or_else(Vec::new, |cached| cached.warnings.clone());
+    let mut walker = Walker::new(&root.path, options, cancel, cached_warnings);
+    let cached_folders = cached.map_or(&[][..], |cached| &cached.folders);
+    let mtime = listing_mtime(modified(&root.path), &rules);
+    let (entries, other_files, dirs) = match cached {
+        Some(cached) if mtime.is_some() && cached.mtime == mtime => {
+            walker.reuse_listing(
+                &root.path,
+                cached.entries.len() + cached.other_files.len() + cached.folders.len(),
+            );
+            let dirs = cached_folders
+                .iter()
+                .map(|folder| (root.path.join(&folder.name), false))
+                .collect();
+            let (entries, other_files) =
+                walker.refresh_files(&root.path, &cached.entries, &cached.other_files);
+            (entries, other_files, dirs)
+        }
+        _ => {
+            let mut entries = Vec::new();
+            let mut other_files = Vec::new();
+            let mut dirs = Vec::new();
+            for child in walker.children(&root.path, &rules) {
+                match child {
+                    Child::File(path) => walker.add_file(&path, &mut entries, &mut other_files),
+                    Child::Dir { path, is_symlink } => dirs.push((path, is_symlink)),
+                }
+            }
+            (entries, other_files, dirs)
+        }
     };
+    // Cached folders that are gone, so whoever shows the cached tree can drop them
+    let gone = cached_folders
+        .iter()
+        .filter(|folder| {
+            !dirs
+                .iter()
+                .any(|(path, _)| file_name_string(path) == folder.name)
+        })
+        .map(|folder| (folder.name.clone(), None))
+        .collect();
+    report(TreeUpdate {
+        root: root.path.clone(),
+        root_entries: Some(entries),
+        root_other_files: other_files,
+        root_mtime: mtime,
+        folders: gone,
+        warnings: std::mem::take(&mut walker.warnings),
+    });
+
+    for (path, is_symlink) in dirs {
+        if walker.is_cancelled() {
+            return;
+        }
+        let name = file_name_string(&path);
+        let cached_folder = cached_folders.iter().find(|folder| folder.name == name);
+        let folder = walker.read_subfolder(&path, is_symlink, &rules, 1, cached_folder);
+        if folder.is_none() && cached_folder.is_none() {
+            continue;
+        }
+        report(TreeUpdate {
+            root: root.path.clone(),
+            root_entries: None,
+            root_other_files: Vec::new(),
+            root_mtime: None,
+            folders: vec![(name, folder)],
+            warnings: std::mem::take(&mut walker.warnings),
+        });
+    }
+    // Limits hit in folders that were then left out entirely
+    if !walker.warnings.is_empty() {
+        report(TreeUpdate {
+            root: root.path.clone(),
+            root_entries: None,
+            root_other_files: Vec::new(),
+            root_mtime: None,
+            folders: Vec::new(),
+            warnings: walker.warnings,
+        });
+    }
+}
 
-    let mut entries = Vec::new();
-    let mut folders = Vec::new();
+/// The entry called `id`, in whichever root it lives
+pub fn find_entry<'a>(roots: &'a [RootFolder], id: &EntryId) -> Option<&'a Entry> {
+    roots.iter().find_map(|root| root.find_entry(id))
+}
 
-    for dir_entry in dir_entries.flatten() {
-        let path = dir_entry.path();
+/// Identity of a directory however it was reached
+#[cfg(unix)]
+type DirKey = (u64, u64);
+#[cfg(not(unix))]
+type DirKey = PathBuf;
 
-        if path.is_dir() {
-            let (child_entries, child_folders) = read_children(&path);
-            let mut flat_entries = Vec::new();
-            flatten_entries(
-                &child_entries,
-                &child_folders,
-                PathBuf::new(),
-                &mut flat_entries,
-            );
-            folders.push(Folder {
-                name: file_name_string(&path),
-                entries: child_entries,
-                folders: child_folders,
-                flat_entries,
+#[cfg(unix)]
+fn dir_key(_path: &Path, metadata: &std::fs::Metadata) -> Option<DirKey> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn dir_key(path: &Path, _metadata: &std::fs::Metadata) -> Option<DirKey> {
+    path.canonicalize().ok()
+}
+
+/// A directory entry that survived the hidden, ignore and symlink rules
+enum Child {
+    Dir {
+        path: PathBuf,
+        is_symlink: bool,
+    },
+    /// A file for the providers to read
+    File(PathBuf),
+}
+
+/// State of one walk below a root, so limits hold across the whole walk
+struct Walker<'a> {
+    options: &'a ScanOptions,
+    /// Set from another thread to abandon the walk
+    cancel: &'a AtomicBool,
+    /// Directories already scanned; reaching one again means a symlink (or bind mount) loop
+    visited: HashSet<DirKey>,
+    /// Directory entries looked at so far, counted against `max_entries`
+    examined: usize,
+    warnings: Vec<ScanWarning>,
+    /// Warnings from the cached scan, kept for the folders whose listing is reused
+    cached_warnings: Vec<ScanWarning>,
+    /// Asked in turn about every file found
+    providers: Vec<&'static dyn Provider>,
+}
+
+impl<'a> Walker<'a> {
+    fn new(
+        root: &Path,
+        options: &'a ScanOptions,
+        cancel: &'a AtomicBool,
+        cached_warnings: Vec<ScanWarning>,
+    ) -> Self {
+        let root_key = std::fs::metadata(root)
+            .ok()
+            .and_then(|metadata| dir_key(root, &metadata));
+        Walker {
+            options,
+            cancel,
+            visited: root_key.into_iter().collect(),
+            examined: 0,
+            warnings: Vec::new(),
+            cached_warnings,
+            providers: file_providers(&options.task_runners),
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    /// Whether the walk should stop, because it was cancelled or reached the entry cap. The
+    /// first time the cap is reached, a warning is recorded for `dir`.
+    fn out_of_entries(&mut self, dir: &Path) -> bool {
+        if self.is_cancelled() {
+            return true;
+        }
+        if self.examined < self.options.max_entries {
+            self.examined += 1;
+            return false;
+        }
+        let already_warned = self
+            .warnings
+            .iter()
+            .any(|warning| matches!(warning, ScanWarning::TooManyEntries { .. }));
+        if !already_warned {
+            self.warnings.push(ScanWarning::TooManyEntries {
+                path: dir.to_path_buf(),
             });
-        } else if is_executable(&path) {
-            entries.push(Entry {
-                name: file_name_string(&path),
-                executable_path: path,
+        }
+        true
+    }
+
+    /// The entries read from the file at `path` by the first provider that reads such files
+    fn read_file(&self, path: &Path) -> Vec<Entry> {
+        self.providers
+            .iter()
+            .find_map(|provider| provider.read_file(path))
+            .unwrap_or_default()
+    }
+
+    /// Add the entries read from the file at `path` to `entries`, or its name to `other_files`
+    /// if there are none
+    fn add_file(&self, path: &Path, entries: &mut Vec<Entry>, other_files: &mut Vec<String>) {
+        let read = self.read_file(path);
+        if read.is_empty() {
+            other_files.push(file_name_string(path));
+        } else {
+            entries.extend(read);
+        }
+    }
+
+    /// Cached entries and other files of the unchanged folder `dir`, with the files edited or
+    /// made executable or not since read again
+    fn refresh_files(
+        &self,
+        dir: &Path,
+        cached_entries: &[Entry],
+        cached_other_files: &[String],
+    ) -> (Vec<Entry>, Vec<String>) {
+        let mut entries = Vec::new();
+        let mut other_files = Vec::new();
+        let mut reread: Vec<&Path> = Vec::new();
+        for entry in cached_entries {
+            if entry.is_current() {
+                entries.push(entry.clone());
+            } else if !reread.contains(&entry.path.as_path()) {
+                // A task file defines several entries; editing it can add or remove some
+                reread.push(&entry.path);
+                self.add_file(&entry.path, &mut entries, &mut other_files);
+            }
+        }
+        // Cheap to ask again: the providers go by file name and mode before opening anything
+        for name in cached_other_files {
+            self.add_file(&dir.join(name), &mut entries, &mut other_files);
+        }
+        (entries, other_files)
+    }
+
+    /// Account for a cached listing of `dir` with `len` children as if it had been read again
+    fn reuse_listing(&mut self, dir: &Path, len: usize) {
+        self.examined += len;
+        let warnings = self
+            .cached_warnings
+            .iter()
+            .filter(|warning| warning.dir() == dir)
+            .cloned();
+        self.warnings.extend(warnings);
+    }
+
+    /// Sort out the entries of `dir` that the scan should look at, in no particular order
+    fn children(&mut self, dir: &Path, rules: &IgnoreRules) -> Vec<Child> {
+        let Ok(dir_entries) = std::fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        let mut children = Vec::new();
+        for dir_entry in dir_entries.flatten() {
+            if self.out_of_entries(dir) {
+                break;
+            }
+            let path = dir_entry.path();
+            if !self.options.include_hidden && is_hidden(&path) {
+                continue;
+            }
+            let Ok(file_type) = dir_entry.file_type() else {
+                continue;
+            };
+            let is_symlink = file_type.is_symlink();
+            // Only stat through the link when it is one; read_dir already knows the rest
+            let is_dir = if is_symlink {
+                path.is_dir()
+            } else {
+                file_type.is_dir()
+            };
+            if rules.is_ignored(&path, is_dir) {
+                continue;
+            }
+
+            if is_dir {
+                children.push(Child::Dir { path, is_symlink });
+            } else if !is_symlink || self.options.follow_file_symlinks {
+                children.push(Child::File(path));
+            }
+        }
+        children
+    }
+
+    /// Entries, other files and folders in `dir`, which lies `depth` levels below its root;
+    /// `rules` are the ignore rules in effect inside `dir`. Subfolders also found in `cached` are
+    /// checked against their cached listing.
+    fn read_children(
+        &mut self,
+        dir: &Path,
+        rules: &IgnoreRules,
+        depth: usize,
+        cached: &[Folder],
+    ) -> (Vec<Entry>, Vec<String>, Vec<Folder>) {
+        let mut entries = Vec::new();
+        let mut other_files = Vec::new();
+        let mut folders = Vec::new();
+        for child in self.children(dir, rules) {
+            match child {
+                Child::Dir { path, is_symlink } => {
+                    let name = file_name_string(&path);
+                    let cached = cached.iter().find(|folder| folder.name == name);
+                    if let Some(folder) =
+                        self.read_subfolder(&path, is_symlink, rules, depth + 1, cached)
+                    {
+                        folders.push(folder);
+                    }
+                }
+                Child::File(path) => self.add_file(&path, &mut entries, &mut other_files),
+            }
+        }
+        folders.sort_by_cached_key(|folder| folder.name.to_lowercase());
+        (entries, other_files, folders)
+    }
+
+    /// The cached listing of `dir`, known to be unchanged, with its subfolders checked in turn
+    fn reuse_children(
+        &mut self,
+        dir: &Path,
+        rules: &IgnoreRules,
+        depth: usize,
+        cached: &Folder,
+    ) -> (Vec<Entry>, Vec<String>, Vec<Folder>) {
+        self.reuse_listing(
+            dir,
+            cached.entries.len() + cached.other_files.len() + cached.folders.len(),
+        );
+        let mut folders = Vec::new();
+        for cached in &cached.folders {
+            if self.is_cancelled() {
+                break;
+            }
+            let path = dir.join(&cached.name);
+            if let Some(folder) = self.read_subfolder(&path, false, rules, depth + 1, Some(cached))
+            {
+                folders.push(folder);
+            }
+        }
+        let (entries, other_files) = self.refresh_files(dir, &cached.entries, &cached.other_files);
+        (entries, other_files, folders)
+    }
+
+    /// Scan the folder at `path`, `depth` levels below its root, unless a limit says otherwise;
+    /// `rules` are those of its parent. `cached` is the folder as an earlier scan found it.
+    fn read_subfolder(
+        &mut self,
+        path: &Path,
+        is_symlink: bool,
+        rules: &IgnoreRules,
+        depth: usize,
+        cached: Option<&Folder>,
+    ) -> Option<Folder> {
+        if is_symlink && !self.options.follow_dir_symlinks {
+            return None;
+        }
+        if depth > self.options.max_depth {
+            // One warning per folder at the limit, not one per subfolder
+            let warning = ScanWarning::TooDeep {
+                path: path.parent().unwrap_or(path).to_path_buf(),
+            };
+            if self.warnings.last() != Some(&warning) {
+                self.warnings.push(warning);
+            }
+            return None;
+        }
+        // The folder vanished since it was listed, e.g. while reusing a cached listing
+        let metadata = std::fs::metadata(path).ok()?;
+        if let Some(key) = dir_key(path, &metadata)
+            && !self.visited.insert(key)
+        {
+            self.warnings.push(ScanWarning::Loop {
+                path: path.to_path_buf(),
             });
+            return None;
         }
+
+        // Read before listing, so a change made during the listing shows up next time
+        let rules = rules.descend(path);
+        let mtime = listing_mtime(metadata.modified().ok(), &rules);
+        let (entries, other_files, folders) = match cached {
+            Some(cached) if mtime.is_some() && cached.mtime == mtime => {
+                self.reuse_children(path, &rules, depth, cached)
+            }
+            _ => {
+                let cached_folders = cached.map_or(&[][..], |cached| &cached.folders);
+                self.read_children(path, &rules, depth, cached_folders)
+            }
+        };
+        let mut flat_entries = Vec::new();
+        flatten_entries(&entries, &folders, PathBuf::new(), &mut flat_entries);
+        Some(Folder {
+            name: file_name_string(path),
+            mtime,
+            entries,
+            other_files,
+            folders,
+            flat_entries,
+        })
     }
+}
 
-    (entries, folders)
+/// Parts of the tree re-read after files changed under the root
+pub struct TreeUpdate {
+    /// Path of the root the update belongs to
+    pub root: PathBuf,
+    /// New executables directly in the root, if any of them changed
+    pub root_entries: Option<Vec<Entry>>,
+    /// Files directly in the root that no provider read, alongside `root_entries`
+    pub root_other_files: Vec<String>,
+    /// Modification time of the root when `root_entries` were read
+    pub root_mtime: Option<SystemTime>,
+    /// Top-level folders to replace, by name; `None` when the folder is gone
+    pub folders: Vec<(String, Option<Folder>)>,
+    /// Limits hit while re-reading
+    pub warnings: Vec<ScanWarning>,
+}
+
+/// Re-read only the top-level folders (and root entries) that contain one of `changed`.
+pub fn rebuild_changed(root_path: &Path, changed: &[PathBuf], options: &ScanOptions) -> TreeUpdate {
+    let mut root_dirty = false;
+    let mut dirty_folders: Vec<String> = Vec::new();
+    for path in changed {
+        let Ok(relative) = path.strip_prefix(root_path) else {
+            continue;
+        };
+        let mut components = relative.components();
+        let Some(first) = components.next() else {
+            // The root itself changed, e.g. it was recreated
+            root_dirty = true;
+            continue;
+        };
+        if components.next().is_none() {
+            // Could be a file directly in the root or a whole folder added/removed
+            root_dirty = true;
+        }
+        let name = first.as_os_str().to_string_lossy().into_owned();
+        if !dirty_folders.contains(&name) {
+            dirty_folders.push(name);
+        }
+    }
+    if dirty_folders.iter().any(|name| name == IGNORE_FILE_NAME) {
+        // The root's own ignore file applies to every folder
+        root_dirty = true;
+        let subdirs = std::fs::read_dir(root_path).into_iter().flatten().flatten();
+        for dir_entry in subdirs.filter(|dir_entry| dir_entry.path().is_dir()) {
+            let name = dir_entry.file_name().to_string_lossy().into_owned();
+            if !dirty_folders.contains(&name) {
+                dirty_folders.push(name);
+            }
+        }
+    }
+
+    let rules = IgnoreRules::for_root(root_path, &options.ignore_patterns);
+    let never_cancelled = AtomicBool::new(false);
+    let mut walker = Walker::new(root_path, options, &never_cancelled, Vec::new());
+    let root_mtime = root_dirty
+        .then(|| listing_mtime(modified(root_path), &rules))
+        .flatten();
+    let mut root_other_files = Vec::new();
+    let root_entries = root_dirty.then(|| {
+        let mut entries = Vec::new();
+        for child in walker.children(root_path, &rules) {
+            if let Child::File(path) = child {
+                walker.add_file(&path, &mut entries, &mut root_other_files);
+            }
+        }
+        entries
+    });
+    let folders = dirty_folders
+        .into_iter()
+        .map(|name| {
+            let path = root_path.join(&name);
+            let is_symlink = path.symlink_metadata().is_ok_and(|m| m.is_symlink());
+            let visible = (options.include_hidden || !is_hidden(&path))
+                && path.is_dir()
+                && !rules.is_ignored(&path, true);
+            let folder = visible
+                .then(|| walker.read_subfolder(&path, is_symlink, &rules, 1, None))
+                .flatten();
+            (name, folder)
+        })
+        .collect();
+
+    TreeUpdate {
+        root: root_path.to_path_buf(),
+        root_entries,
+        root_other_files,
+        root_mtime,
+        folders,
+        warnings: walker.warnings,
+    }
+}
+
+/// What decides whether the listing of a folder can be reused: the folder's modification time, or
+/// that of the newest ignore file in effect in it when that is later, as editing an ignore file in
+/// place leaves the folders it applies to untouched
+fn listing_mtime(dir_mtime: Option<SystemTime>, rules: &IgnoreRules) -> Option<SystemTime> {
+    dir_mtime.map(|dir_mtime| dir_mtime.max(rules.newest_file().unwrap_or(dir_mtime)))
 }
 
 fn flatten_entries(
@@ -87,13 +887,16 @@ fn flatten_entries(
 ) {
     for entry in entries {
         let display_name = if prefix.as_os_str().is_empty() {
-            entry.name.clone()
+            entry.label().to_owned()
         } else {
-            prefix.join(&entry.name).to_string_lossy().into_owned()
+            prefix.join(entry.label()).to_string_lossy().into_owned()
         };
         results.push(FlatEntry {
             display_name,
-            executable_path: entry.executable_path.clone(),
+            name: entry.name.clone(),
+            id: entry.id.clone(),
+            path: entry.path.clone(),
+            meta: entry.meta.clone(),
         });
     }
     for folder in folders {
@@ -106,9 +909,218 @@ fn flatten_entries(
     }
 }
 
-fn file_name_string(path: &Path) -> String {
+/// Dot-files and dot-folders, hidden by convention on Unix
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
+
+pub(crate) fn file_name_string(path: &Path) -> String {
     path.file_name()
         .unwrap_or_default()
         .to_string_lossy()
         .into_owned()
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::utils::temp_dir::TempDir;
+    use std::os::unix::fs::PermissionsExt;
+    use std::time::Duration;
+
+    fn write_file(path: &Path, contents: &str, mode: u32) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+        set_mode(path, mode);
+    }
+
+    fn set_mode(path: &Path, mode: u32) {
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)).unwrap();
+    }
+
+    /// Rewrite the file at `path` in place, so its folder keeps its modification time, and date
+    /// the change `seconds` into the future to stay clear of the timestamp granularity
+    fn edit_in_place(path: &Path, contents: &str, seconds: u64) {
+        std::fs::write(path, contents).unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(seconds))
+            .unwrap();
+    }
+
+    /// Scan `root` again the way the launcher does at startup, starting from `cached`
+    fn rescan(root: &ScriptRoot, cached: &RootFolder) -> RootFolder {
+        let mut tree = cached.clone();
+        scan_root(
+            root,
+            &ScanOptions::default(),
+            Some(cached),
+            &AtomicBool::new(false),
+            &mut |update| tree.apply(update),
+        );
+        tree
+    }
+
+    fn entry_names(tree: &RootFolder) -> Vec<String> {
+        let mut names: Vec<String> = tree
+            .entries
+            .iter()
+            .map(|entry| entry.name.clone())
+            .chain(
+                tree.folders
+                    .iter()
+                    .flat_map(|folder| &folder.flat_entries)
+                    .map(|entry| entry.name.clone()),
+            )
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn cached_listing_notices_mode_changes() {
+        let dir = TempDir::new("mode-changes");
+        let root = ScriptRoot::new(dir.0.clone());
+        write_file(&dir.0.join("top.sh"), "#!/bin/sh\n", 0o644);
+        write_file(&dir.0.join("sub/was_executable.sh"), "#!/bin/sh\n", 0o755);
+        write_file(&dir.0.join("sub/now_executable.sh"), "#!/bin/sh\n", 0o644);
+        let cached = build_root(&root, &ScanOptions::default());
+        assert_eq!(entry_names(&cached), ["was_executable.sh"]);
+
+        set_mode(&dir.0.join("top.sh"), 0o755);
+        set_mode(&dir.0.join("sub/was_executable.sh"), 0o644);
+        set_mode(&dir.0.join("sub/now_executable.sh"), 0o755);
+        let rescanned = rescan(&root, &cached);
+        assert_eq!(rescanned.mtime, cached.mtime, "the root listing is reused");
+        assert_eq!(entry_names(&rescanned), ["now_executable.sh", "top.sh"]);
+    }
+
+    #[test]
+    fn cached_listing_notices_edited_ignore_files() {
+        let dir = TempDir::new("ignore-edits");
+        let root = ScriptRoot::new(dir.0.clone());
+        write_file(&dir.0.join(IGNORE_FILE_NAME), "", 0o644);
+        write_file(&dir.0.join("sub").join(IGNORE_FILE_NAME), "", 0o644);
+        write_file(&dir.0.join("sub/own.sh"), "#!/bin/sh\n", 0o755);
+        write_file(&dir.0.join("sub/deeper/ancestor.sh"), "#!/bin/sh\n", 0o755);
+        let cached = build_root(&root, &ScanOptions::default());
+        assert_eq!(entry_names(&cached), ["ancestor.sh", "own.sh"]);
+
+        // The folder's own ignore file
+        edit_in_place(&dir.0.join("sub").join(IGNORE_FILE_NAME), "own.sh\n", 10);
+        let cached = rescan(&root, &cached);
+        assert_eq!(entry_names(&cached), ["ancestor.sh"]);
+
+        // An ignore file further up
+        edit_in_place(&dir.0.join(IGNORE_FILE_NAME), "ancestor.sh\n", 20);
+        let cached = rescan(&root, &cached);
+        assert!(entry_names(&cached).is_empty());
+
+        // Unchanged since, so the listings are reused as they are
+        let rescanned = rescan(&root, &cached);
+        assert_eq!(rescanned.folders[0].mtime, cached.folders[0].mtime);
+        assert!(entry_names(&rescanned).is_empty());
+    }
+
+    fn script(path: &Path) {
+        write_file(path, "#!/bin/sh\n", 0o755);
+    }
+
+    #[test]
+    fn link_back_to_an_ancestor_is_reported_as_a_loop() {
+        let dir = TempDir::new("symlink-loop");
+        let root = ScriptRoot::new(dir.0.clone());
+        script(&dir.0.join("a/inner.sh"));
+        std::os::unix::fs::symlink(&dir.0, dir.0.join("a/back")).unwrap();
+        let options = ScanOptions {
+            follow_dir_symlinks: true,
+            ..ScanOptions::default()
+        };
+
+        let tree = build_root(&root, &options);
+        assert_eq!(entry_names(&tree), ["inner.sh"]);
+        assert_eq!(
+            tree.warnings,
+            [ScanWarning::Loop {
+                path: dir.0.join("a/back")
+            }]
+        );
+        assert!(!tree.is_truncated());
+    }
+
+    #[test]
+    fn depth_zero_scans_only_the_root() {
+        let dir = TempDir::new("depth-zero");
+        let root = ScriptRoot::new(dir.0.clone());
+        script(&dir.0.join("top.sh"));
+        script(&dir.0.join("a/nested.sh"));
+        let options = ScanOptions {
+            max_depth: 0,
+            ..ScanOptions::default()
+        };
+
+        let tree = build_root(&root, &options);
+        assert_eq!(entry_names(&tree), ["top.sh"]);
+        assert!(tree.folders.is_empty());
+        assert_eq!(
+            tree.warnings,
+            [ScanWarning::TooDeep {
+                path: dir.0.clone()
+            }]
+        );
+    }
+
+    #[test]
+    fn depth_limit_keeps_folders_down_to_it() {
+        let dir = TempDir::new("depth-limit");
+        let root = ScriptRoot::new(dir.0.clone());
+        script(&dir.0.join("a/one.sh"));
+        script(&dir.0.join("a/b/two.sh"));
+        script(&dir.0.join("a/b/c/three.sh"));
+        script(&dir.0.join("a/b/d/four.sh"));
+        let options = ScanOptions {
+            max_depth: 2,
+            ..ScanOptions::default()
+        };
+
+        let tree = build_root(&root, &options);
+        assert_eq!(entry_names(&tree), ["one.sh", "two.sh"]);
+        // One warning for the folder at the limit, not one per subfolder left out
+        assert_eq!(
+            tree.warnings,
+            [ScanWarning::TooDeep {
+                path: dir.0.join("a/b")
+            }]
+        );
+    }
+
+    #[test]
+    fn entry_cap_truncates_the_scan_and_says_where() {
+        let dir = TempDir::new("entry-cap");
+        let root = ScriptRoot::new(dir.0.clone());
+        for index in 0..10 {
+            script(&dir.0.join(format!("script{index}.sh")));
+        }
+        let options = ScanOptions {
+            max_entries: 4,
+            ..ScanOptions::default()
+        };
+
+        let tree = build_root(&root, &options);
+        assert_eq!(tree.entry_count(), 4);
+        assert!(tree.is_truncated());
+        assert_eq!(
+            tree.warnings,
+            [ScanWarning::TooManyEntries {
+                path: dir.0.clone()
+            }]
+        );
+
+        let everything = build_root(&root, &ScanOptions::default());
+        assert_eq!(everything.entry_count(), 10);
+        assert!(!everything.is_truncated());
+    }
+}
@@ -0,0 +1,440 @@
+use std::borrow::Cow;
+use std::ffi::{OsStr, OsString};
+
+/// Syntax a command line is written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    /// `sh`, `bash`, `zsh` and friends
+    Posix,
+    PowerShell,
+}
+
+impl Shell {
+    /// The shell scripts are launched through on this platform
+    pub fn native() -> Self {
+        if cfg!(windows) {
+            Shell::PowerShell
+        } else {
+            Shell::Posix
+        }
+    }
+}
+
+/// Quote `arg` so `shell` reads it back as a single argument, unchanged
+pub fn quote(shell: Shell, arg: &OsStr) -> OsString {
+    match shell {
+        Shell::Posix => from_bytes(quote_posix(&to_bytes(arg))),
+        Shell::PowerShell => quote_powershell(&arg.to_string_lossy()).into(),
+    }
+}
+
+/// `program` and `args` as one command line for `shell`
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub fn command_line<S: AsRef<OsStr>>(shell: Shell, program: &OsStr, args: &[S]) -> OsString {
+    let mut line = OsString::new();
+    if shell == Shell::PowerShell {
+        // A quoted program is just a string to PowerShell unless it is invoked with `&`
+        line.push("& ");
+    }
+    line.push(quote(shell, program));
+    for arg in args {
+        line.push(" ");
+        line.push(quote(shell, arg.as_ref()));
+    }
+    line
+}
+
+/// Like [`quote`], but always valid UTF-8, for file names that have to travel as text: on Unix,
+/// bytes that aren't UTF-8 are written as `printf` octal escapes, which any POSIX shell turns back
+/// into the original bytes
+pub fn quote_text(shell: Shell, arg: &OsStr) -> String {
+    match shell {
+        Shell::Posix => quote_posix_text(&to_bytes(arg)),
+        Shell::PowerShell => quote_powershell(&arg.to_string_lossy()),
+    }
+}
+
+/// Like [`command_line`], but always valid UTF-8, for the clipboard or another text channel
+pub fn command_line_text<S: AsRef<OsStr>>(shell: Shell, program: &OsStr, args: &[S]) -> String {
+    let words: Vec<String> = std::iter::once(quote_text(shell, program))
+        .chain(args.iter().map(|arg| quote_text(shell, arg.as_ref())))
+        .collect();
+    let prefix = if shell == Shell::PowerShell { "& " } else { "" };
+    format!("{prefix}{}", words.join(" "))
+}
+
+/// `text` as an AppleScript string literal, quotes included
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub fn applescript_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Split `text` into words like a POSIX shell, honouring single quotes, double quotes and
+/// backslash escapes but expanding nothing
+pub fn split_words(text: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err("unclosed single quote".to_owned()),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err("unclosed double quote".to_owned()),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err("unclosed double quote".to_owned()),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some(c) => word.get_or_insert_with(String::new).push(c),
+                None => return Err("trailing backslash".to_owned()),
+            },
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+/// Bytes that never mean anything special to a POSIX shell, so a word made only of them can go
+/// unquoted. `=` and `~` are left out as they are special at the start of a word.
+fn is_posix_safe(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b"_-+./,:@%".contains(&byte)
+}
+
+fn quote_posix(arg: &[u8]) -> Vec<u8> {
+    if !arg.is_empty() && arg.iter().copied().all(is_posix_safe) {
+        return arg.to_vec();
+    }
+    // Nothing is special inside single quotes, and a single quote itself is written as '\''
+    let mut quoted = vec![b'\''];
+    for &byte in arg {
+        if byte == b'\'' {
+            quoted.extend_from_slice(b"'\\''");
+        } else {
+            quoted.push(byte);
+        }
+    }
+    quoted.push(b'\'');
+    quoted
+}
+
+fn quote_posix_text(arg: &[u8]) -> String {
+    if arg.is_empty() {
+        return "''".to_owned();
+    }
+    let mut quoted = String::new();
+    // Runs of invalid bytes come in several chunks; collect them into one `printf`
+    let mut escapes = String::new();
+    for chunk in arg.utf8_chunks() {
+        if !chunk.valid().is_empty() {
+            flush_escapes(&mut quoted, &mut escapes);
+            let valid = quote_posix(chunk.valid().as_bytes());
+            quoted.push_str(&String::from_utf8(valid).expect("quoting keeps UTF-8 valid"));
+        }
+        for byte in chunk.invalid() {
+            escapes.push_str(&format!("\\{byte:03o}"));
+        }
+    }
+    flush_escapes(&mut quoted, &mut escapes);
+    quoted
+}
+
+fn flush_escapes(quoted: &mut String, escapes: &mut String) {
+    if !escapes.is_empty() {
+        quoted.push_str(&format!("\"$(printf '{escapes}')\""));
+        escapes.clear();
+    }
+}
+
+/// PowerShell reads the typographic single quotes as quotes too, so they are doubled as well
+fn quote_powershell(arg: &str) -> String {
+    let mut quoted = String::from("'");
+    for c in arg.chars() {
+        if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201a}' | '\u{201b}') {
+            quoted.push(c);
+        }
+        quoted.push(c);
+    }
+    quoted.push('\'');
+    quoted
+}
+
+#[cfg(unix)]
+fn to_bytes(arg: &OsStr) -> Cow<'_, [u8]> {
+    use std::os::unix::ffi::OsStrExt;
+    Cow::Borrowed(arg.as_bytes())
+}
+
+#[cfg(not(unix))]
+fn to_bytes(arg: &OsStr) -> Cow<'_, [u8]> {
+    match arg.to_string_lossy() {
+        Cow::Borrowed(text) => Cow::Borrowed(text.as_bytes()),
+        Cow::Owned(text) => Cow::Owned(text.into_bytes()),
+    }
+}
+
+#[cfg(unix)]
+fn from_bytes(bytes: Vec<u8>) -> OsString {
+    use std::os::unix::ffi::OsStringExt;
+    OsString::from_vec(bytes)
+}
+
+#[cfg(not(unix))]
+fn from_bytes(bytes: Vec<u8>) -> OsString {
+    String::from_utf8_lossy(&bytes).into_owned().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Names that break naive interpolation into a shell command
+    const HOSTILE: &[&str] = &[
+        "plain",
+        "with space",
+        "  leading and trailing  ",
+        "it's",
+        "''",
+        "\\'",
+        "double\"quote",
+        "back\\slash",
+        "$HOME",
+        "${PATH}",
+        "$(touch pwned)",
+        "`id`",
+        "semi;colon",
+        "pipe|and&amp",
+        "redirect>out<in",
+        "glob*?[ab]",
+        "{a,b}",
+        "~tilde",
+        "=equals",
+        "var=value",
+        "#hash",
+        "!bang",
+        "-rf",
+        "--",
+        "new\nline",
+        "tab\there",
+        "ünïcödé 🚀",
+        "",
+    ];
+
+    /// Have `sh` print each word of `line` followed by a NUL byte
+    #[cfg(unix)]
+    fn sh_words(line: &OsStr) -> Vec<u8> {
+        let mut script = OsString::from("printf '%s\\0' ");
+        script.push(line);
+        let output = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(script)
+            .output()
+            .expect("sh must be available");
+        assert!(output.status.success(), "sh failed for {line:?}");
+        output.stdout
+    }
+
+    #[cfg(unix)]
+    fn nul_terminated(words: &[&[u8]]) -> Vec<u8> {
+        words
+            .iter()
+            .flat_map(|word| [*word, b"\0"].concat())
+            .collect()
+    }
+
+    #[cfg(unix)]
+    fn non_utf8_name() -> OsString {
+        use std::os::unix::ffi::OsStringExt;
+        OsString::from_vec(b"bad \xff\xfe name's".to_vec())
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn posix_quote_round_trips_through_sh() {
+        for name in HOSTILE {
+            let quoted = quote(Shell::Posix, OsStr::new(name));
+            assert_eq!(
+                sh_words(&quoted),
+                nul_terminated(&[name.as_bytes()]),
+                "{name:?}"
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn posix_quote_text_round_trips_through_sh() {
+        for name in HOSTILE {
+            let quoted = quote_text(Shell::Posix, OsStr::new(name));
+            assert_eq!(
+                sh_words(OsStr::new(&quoted)),
+                nul_terminated(&[name.as_bytes()]),
+                "{name:?}"
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn non_utf8_bytes_survive_both_forms() {
+        use std::os::unix::ffi::OsStrExt;
+        let name = non_utf8_name();
+        let expected = nul_terminated(&[name.as_bytes()]);
+        assert_eq!(sh_words(&quote(Shell::Posix, &name)), expected);
+
+        let text = quote_text(Shell::Posix, &name);
+        assert!(text.contains("printf '\\377\\376'"), "{text}");
+        assert_eq!(sh_words(OsStr::new(&text)), expected);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn command_line_keeps_every_argument_separate() {
+        let line = command_line(Shell::Posix, OsStr::new("printf"), &["%s\\0"]);
+        let mut line = line;
+        for name in HOSTILE {
+            line.push(" ");
+            line.push(quote(Shell::Posix, OsStr::new(name)));
+        }
+        let output = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(&line)
+            .output()
+            .expect("sh must be available");
+        let words: Vec<&[u8]> = HOSTILE.iter().map(|name| name.as_bytes()).collect();
+        assert_eq!(output.stdout, nul_terminated(&words));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn runs_scripts_with_hostile_file_names() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = std::env::temp_dir().join(format!("quick_launch_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let names = HOSTILE
+            .iter()
+            .filter(|name| !name.is_empty() && !name.contains('/'))
+            .map(OsString::from)
+            .chain([non_utf8_name()]);
+        for name in names {
+            let path = dir.join(&name);
+            std::fs::write(&path, "#!/bin/sh\nprintf '%s\\0' \"$@\"\n").unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+            let args = ["one arg", "$two", "it's"];
+            for line in [
+                command_line(Shell::Posix, path.as_os_str(), &args),
+                command_line_text(Shell::Posix, path.as_os_str(), &args).into(),
+            ] {
+                let output = std::process::Command::new("sh")
+                    .arg("-c")
+                    .arg(&line)
+                    .output()
+                    .unwrap();
+                assert_eq!(
+                    output.stdout,
+                    nul_terminated(&[b"one arg", b"$two", b"it's"]),
+                    "{name:?}"
+                );
+            }
+            std::fs::remove_file(&path).unwrap();
+        }
+        std::fs::remove_dir(&dir).unwrap();
+    }
+
+    #[test]
+    fn safe_words_stay_bare_and_empty_words_are_quoted() {
+        assert_eq!(
+            quote_text(Shell::Posix, OsStr::new("/usr/bin/env")),
+            "/usr/bin/env"
+        );
+        assert_eq!(
+            quote_text(Shell::Posix, OsStr::new("a-b_c.d,e:f@g%h+i")),
+            "a-b_c.d,e:f@g%h+i"
+        );
+        assert_eq!(quote_text(Shell::Posix, OsStr::new("")), "''");
+        assert_eq!(quote_text(Shell::Posix, OsStr::new("a b")), "'a b'");
+        assert_eq!(quote_text(Shell::Posix, OsStr::new("it's")), "'it'\\''s'");
+    }
+
+    #[test]
+    fn split_words_reverses_posix_quoting() {
+        let args: Vec<&str> = HOSTILE.to_vec();
+        let line = command_line_text(Shell::Posix, OsStr::new("program"), &args);
+        let words = split_words(&line).unwrap();
+        assert_eq!(words[0], "program");
+        assert_eq!(&words[1..], args.as_slice());
+    }
+
+    #[test]
+    fn split_words_handles_quotes_and_escapes() {
+        assert_eq!(
+            split_words(r#"a "b c" 'd "e"' f\ g "h\"i\n" '' """#).unwrap(),
+            ["a", "b c", "d \"e\"", "f g", "h\"i\\n", "", ""]
+        );
+        assert!(split_words("'open").is_err());
+        assert!(split_words("\"open").is_err());
+        assert!(split_words("trailing\\").is_err());
+        assert!(split_words("   ").unwrap().is_empty());
+    }
+
+    #[test]
+    fn powershell_quotes_are_doubled() {
+        assert_eq!(
+            quote_text(Shell::PowerShell, OsStr::new("plain")),
+            "'plain'"
+        );
+        assert_eq!(quote_text(Shell::PowerShell, OsStr::new("it's")), "'it''s'");
+        assert_eq!(
+            quote_text(Shell::PowerShell, OsStr::new("curly\u{2019}s")),
+            "'curly\u{2019}\u{2019}s'"
+        );
+        assert_eq!(
+            quote_text(Shell::PowerShell, OsStr::new("$env:PATH; `whoami`")),
+            "'$env:PATH; `whoami`'"
+        );
+    }
+
+    #[test]
+    fn powershell_command_lines_invoke_the_program() {
+        assert_eq!(
+            command_line_text(
+                Shell::PowerShell,
+                OsStr::new(r"C:\Scripts\back up.ps1"),
+                &["-Target", "it's"]
+            ),
+            r"& 'C:\Scripts\back up.ps1' '-Target' 'it''s'"
+        );
+    }
+
+    #[test]
+    fn applescript_strings_escape_backslashes_and_quotes() {
+        assert_eq!(applescript_string("plain"), "\"plain\"");
+        assert_eq!(
+            applescript_string(r#"sh -c 'say "hi" \ bye'"#),
+            r#""sh -c 'say \"hi\" \\ bye'""#
+        );
+    }
+}
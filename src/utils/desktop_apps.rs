@@ -0,0 +1,592 @@
+use super::build_tree::{Entry, file_name_string};
+use super::file_ops::modified;
+use super::launch::CommandSpec;
+use super::metadata::ScriptMeta;
+use super::provider::{EntryId, Provider};
+use super::terminal::is_installed;
+use crate::preferences::{AppPreferences, ScriptRoot};
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// Name of the provider listing installed desktop applications
+const DESKTOP_PROVIDER: &str = "desktop";
+/// Where desktop files live below each XDG data directory
+const APPLICATIONS_DIR: &str = "applications";
+/// Used when `XDG_DATA_DIRS` is unset or empty, as the XDG base directory spec says
+const DEFAULT_DATA_DIRS: &str = "/usr/local/share:/usr/share";
+/// Subfolders of an applications folder are vendor prefixes, rarely more than one level deep
+const MAX_DEPTH: usize = 4;
+
+/// Applications installed for the desktop, read from their `.desktop` files
+pub struct DesktopApps;
+
+impl Provider for DesktopApps {
+    fn name(&self) -> &'static str {
+        DESKTOP_PROVIDER
+    }
+
+    /// The application described by the desktop file at `path`, unless the desktop would hide it
+    fn read_file(&self, path: &Path) -> Option<Vec<Entry>> {
+        if path.extension()? != "desktop" {
+            return None;
+        }
+        let file_id = application_dirs()
+            .iter()
+            .find_map(|dir| desktop_file_id(dir, path))
+            .unwrap_or_else(|| file_name_string(path));
+        Some(
+            read_app(path, &file_id, &Environment::current())
+                .into_iter()
+                .collect(),
+        )
+    }
+
+    /// Reads the desktop file again, so the command is the one it holds now. `args` are the
+    /// files or URLs to open, filled in where `Exec` asks for them.
+    fn command(&self, entry: &Entry, args: &[String]) -> CommandSpec {
+        let desktop_entry = std::fs::read_to_string(&entry.path)
+            .map(|text| DesktopEntry::parse(&text))
+            .unwrap_or_default();
+        let mut argv = desktop_entry
+            .command_line(entry.label(), &entry.path, args)
+            .into_iter()
+            .map(OsString::from);
+        CommandSpec {
+            // Nothing to run; starting the desktop file itself reports the failure
+            program: argv
+                .next()
+                .unwrap_or_else(|| entry.path.as_os_str().to_owned()),
+            args: argv.collect(),
+            cwd: desktop_entry
+                .get("Path")
+                .map(PathBuf::from)
+                .or_else(dirs::home_dir)
+                .unwrap_or_else(|| PathBuf::from("/")),
+            title: entry.label().to_owned(),
+        }
+    }
+
+    fn discover(&self, preferences: &AppPreferences) -> Option<Vec<Entry>> {
+        preferences
+            .desktop_apps
+            .then(|| read_applications(&application_dirs(), &Environment::current()))
+    }
+
+    fn section_label(&self) -> &'static str {
+        "Applications"
+    }
+
+    /// Applications don't live under the roots; their history is kept while they aren't listed,
+    /// so listing them again finds it
+    fn keeps_history(&self, _id: &EntryId, _roots: &[ScriptRoot]) -> bool {
+        true
+    }
+}
+
+/// Every application in the applications folders `dirs` that the desktop shows in its menus,
+/// sorted by name. A desktop file found in several folders is only read from the first, so one in
+/// the user's own folder overrides, or with `Hidden=true` removes, a system-wide one.
+fn read_applications(dirs: &[PathBuf], environment: &Environment) -> Vec<Entry> {
+    let mut seen = HashSet::new();
+    let mut apps = Vec::new();
+    for dir in dirs {
+        let mut files = Vec::new();
+        collect_desktop_files(dir, 0, &mut files);
+        for path in files {
+            let Some(file_id) = desktop_file_id(dir, &path) else {
+                continue;
+            };
+            if seen.insert(file_id.clone()) {
+                apps.extend(read_app(&path, &file_id, environment));
+            }
+        }
+    }
+    apps.sort_by_cached_key(|app| app.label().to_lowercase());
+    apps
+}
+
+/// `applications` below the user's data directory, then below each of `XDG_DATA_DIRS`, in order
+/// of precedence
+fn application_dirs() -> Vec<PathBuf> {
+    let data_dirs = env::var_os("XDG_DATA_DIRS")
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| OsString::from(DEFAULT_DATA_DIRS));
+    dirs::data_dir()
+        .into_iter()
+        .chain(env::split_paths(&data_dirs))
+        // Relative entries are invalid and must be ignored
+        .filter(|dir| dir.is_absolute())
+        .map(|dir| dir.join(APPLICATIONS_DIR))
+        .collect()
+}
+
+fn collect_desktop_files(dir: &Path, depth: usize, files: &mut Vec<PathBuf>) {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for path in read_dir.flatten().map(|dir_entry| dir_entry.path()) {
+        if path.is_dir() {
+            if depth < MAX_DEPTH {
+                collect_desktop_files(&path, depth + 1, files);
+            }
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "desktop")
+        {
+            files.push(path);
+        }
+    }
+}
+
+/// The desktop file ID of `path`: its path below the applications folder `dir`, with `-` for
+/// the separators, e.g. `kde-kate.desktop` for `kde/kate.desktop`
+fn desktop_file_id(dir: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(dir).ok()?;
+    let parts: Vec<String> = relative
+        .components()
+        .map(|part| part.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    Some(parts.join("-"))
+}
+
+fn read_app(path: &Path, file_id: &str, environment: &Environment) -> Option<Entry> {
+    let text = std::fs::read_to_string(path).ok()?;
+    let desktop_entry = DesktopEntry::parse(&text);
+    if !desktop_entry.is_shown(environment) {
+        return None;
+    }
+    let name = desktop_entry.localized("Name", environment)?;
+    let description = desktop_entry
+        .localized("Comment", environment)
+        .or_else(|| desktop_entry.localized("GenericName", environment));
+    let keywords = desktop_entry.localized("Keywords", environment);
+    Some(Entry {
+        id: EntryId::new(DESKTOP_PROVIDER, file_id),
+        name: file_id.to_owned(),
+        path: path.to_path_buf(),
+        meta: ScriptMeta {
+            name: Some(name),
+            description,
+            tags: keywords.as_deref().map(list).unwrap_or_default(),
+            detached: !desktop_entry.is_true("Terminal"),
+            ..ScriptMeta::default()
+        },
+        mtime: modified(path),
+    })
+}
+
+/// What decides whether an application shows and under which name
+struct Environment {
+    /// Locale names to look for localized values under, most specific first
+    locales: Vec<String>,
+    /// Names listed in `XDG_CURRENT_DESKTOP`, for `OnlyShowIn` and `NotShowIn`
+    desktops: Vec<String>,
+}
+
+impl Environment {
+    fn current() -> Self {
+        // The messages locale
+        let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .filter_map(|var| env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default();
+        let desktops = env::var("XDG_CURRENT_DESKTOP").unwrap_or_default();
+        Environment {
+            locales: locale_names(&locale),
+            desktops: list(&desktops.replace(':', ";")),
+        }
+    }
+}
+
+/// The names a localized key is tried under in `locale`: for `sr_RS.UTF-8@latin` that is
+/// `sr_RS@latin`, `sr_RS`, `sr@latin`, `sr`
+fn locale_names(locale: &str) -> Vec<String> {
+    let (locale, modifier) = match locale.split_once('@') {
+        Some((locale, modifier)) => (locale, Some(modifier)),
+        None => (locale, None),
+    };
+    // The encoding plays no part in matching
+    let locale = locale.split('.').next().unwrap_or_default();
+    let (lang, country) = match locale.split_once('_') {
+        Some((lang, country)) => (lang, Some(country)),
+        None => (locale, None),
+    };
+    if lang.is_empty() || lang == "C" || lang == "POSIX" {
+        return Vec::new();
+    }
+    let mut names = Vec::new();
+    if let (Some(country), Some(modifier)) = (country, modifier) {
+        names.push(format!("{lang}_{country}@{modifier}"));
+    }
+    if let Some(country) = country {
+        names.push(format!("{lang}_{country}"));
+    }
+    if let Some(modifier) = modifier {
+        names.push(format!("{lang}@{modifier}"));
+    }
+    names.push(lang.to_owned());
+    names
+}
+
+/// The items of a `;`-separated list value
+fn list(value: &str) -> Vec<String> {
+    value
+        .split(';')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+/// The keys of a desktop file's `[Desktop Entry]` group, with their values unescaped
+#[derive(Default)]
+struct DesktopEntry {
+    keys: HashMap<String, String>,
+}
+
+impl DesktopEntry {
+    fn parse(text: &str) -> Self {
+        let mut keys = HashMap::new();
+        let mut in_group = false;
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('[') {
+                in_group = line == "[Desktop Entry]";
+                continue;
+            }
+            if in_group && let Some((key, value)) = line.split_once('=') {
+                keys.entry(key.trim().to_owned())
+                    .or_insert_with(|| unescape(value.trim()));
+            }
+        }
+        DesktopEntry { keys }
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.keys.get(key).map(String::as_str)
+    }
+
+    fn is_true(&self, key: &str) -> bool {
+        self.get(key) == Some("true")
+    }
+
+    /// The value of `key` for the most specific of the `environment`'s locales that has one
+    fn localized(&self, key: &str, environment: &Environment) -> Option<String> {
+        environment
+            .locales
+            .iter()
+            .find_map(|locale| self.get(&format!("{key}[{locale}]")))
+            .or_else(|| self.get(key))
+            .filter(|value| !value.is_empty())
+            .map(str::to_owned)
+    }
+
+    /// Whether the desktop would list the application in its menus
+    fn is_shown(&self, environment: &Environment) -> bool {
+        let shown_in = |key| {
+            let desktops = list(self.get(key).unwrap_or_default());
+            let matches = environment
+                .desktops
+                .iter()
+                .any(|desktop| desktops.contains(desktop));
+            (desktops.is_empty(), matches)
+        };
+        let (anywhere, only_here) = shown_in("OnlyShowIn");
+        let (_, not_here) = shown_in("NotShowIn");
+        self.get("Type") == Some("Application")
+            && self.get("Exec").is_some()
+            && !self.is_true("NoDisplay")
+            && !self.is_true("Hidden")
+            && (anywhere || only_here)
+            && !not_here
+            && self.get("TryExec").is_none_or(is_installed)
+    }
+
+    /// The arguments `Exec` runs, with its field codes filled in from `args` (the files or URLs
+    /// to open), `name` and the desktop file's `path`. Empty if there is no usable `Exec`.
+    fn command_line(&self, name: &str, path: &Path, args: &[String]) -> Vec<String> {
+        let Some(words) = self.get("Exec").and_then(split_exec) else {
+            return Vec::new();
+        };
+        let mut argv = Vec::new();
+        for word in words {
+            match word.as_str() {
+                "%f" | "%u" => argv.extend(args.first().cloned()),
+                "%F" | "%U" => argv.extend(args.iter().cloned()),
+                "%i" => {
+                    if let Some(icon) = self.get("Icon") {
+                        argv.push("--icon".to_owned());
+                        argv.push(icon.to_owned());
+                    }
+                }
+                // Dropped, so it mustn't leave an empty argument behind
+                codes if only_dropped_field_codes(codes) => {}
+                _ => argv.push(expand_field_codes(&word, name, path, args)),
+            }
+        }
+        argv
+    }
+}
+
+/// Undo the escapes allowed in string values: `\s`, `\n`, `\t`, `\r` and `\\`. Others, such as
+/// `\;` inside lists, are kept.
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => unescaped.push(' '),
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some('r') => unescaped.push('\r'),
+            Some('\\') => unescaped.push('\\'),
+            Some(other) => {
+                unescaped.push('\\');
+                unescaped.push(other);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// Split an `Exec` value into words. Double quotes keep spaces inside one word, and within them
+/// a backslash escapes the next character. `None` if a quote is left open.
+fn split_exec(exec: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quoted = false;
+    let mut chars = exec.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                word.get_or_insert_default();
+            }
+            '\\' if quoted => word.get_or_insert_default().push(chars.next()?),
+            c if c.is_whitespace() && !quoted => words.extend(word.take()),
+            c => word.get_or_insert_default().push(c),
+        }
+    }
+    if quoted {
+        return None;
+    }
+    words.extend(word);
+    Some(words)
+}
+
+/// Whether `word` is made only of deprecated or unknown field codes, such as `%m`
+fn only_dropped_field_codes(word: &str) -> bool {
+    let mut chars = word.chars();
+    let mut any = false;
+    while let Some(c) = chars.next() {
+        let dropped = c == '%'
+            && chars
+                .next()
+                .is_some_and(|code| !matches!(code, '%' | 'f' | 'u' | 'c' | 'k'));
+        if !dropped {
+            return false;
+        }
+        any = true;
+    }
+    any
+}
+
+/// Fill in the field codes inside a word. Codes that only make sense as a word of their own are
+/// handled by [`DesktopEntry::command_line`]; deprecated and unknown ones are dropped.
+fn expand_field_codes(word: &str, name: &str, path: &Path, args: &[String]) -> String {
+    let mut expanded = String::with_capacity(word.len());
+    let mut chars = word.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => expanded.push('%'),
+            Some('f' | 'u') => expanded.push_str(args.first().map_or("", String::as_str)),
+            Some('c') => expanded.push_str(name),
+            Some('k') => expanded.push_str(&path.to_string_lossy()),
+            _ => {}
+        }
+    }
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn environment(locales: &[&str], desktops: &[&str]) -> Environment {
+        Environment {
+            locales: locales.iter().map(|name| name.to_string()).collect(),
+            desktops: desktops.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+
+    /// A shown application, with the keys in `extra` coming first so they win over the defaults
+    fn app(extra: &str) -> DesktopEntry {
+        DesktopEntry::parse(&format!(
+            "[Desktop Entry]\n{extra}Type=Application\nName=App\nExec=app\n"
+        ))
+    }
+
+    #[test]
+    fn split_exec_handles_quotes_and_escapes() {
+        assert_eq!(
+            split_exec(r#"app --flag "two words" "a \"quote\"" "" end"#).unwrap(),
+            ["app", "--flag", "two words", "a \"quote\"", "", "end"]
+        );
+        assert_eq!(split_exec(r#""back\\slash\$""#).unwrap(), [r"back\slash$"]);
+        assert_eq!(split_exec("  spaced   out  ").unwrap(), ["spaced", "out"]);
+        assert_eq!(split_exec(r#"app "unclosed"#), None);
+    }
+
+    #[test]
+    fn unescape_handles_string_escapes_only() {
+        assert_eq!(unescape(r"a\sb\nc\td\re\\f"), "a b\nc\td\re\\f");
+        assert_eq!(unescape(r"keep\;this"), r"keep\;this");
+        assert_eq!(unescape(r"trailing\"), r"trailing\");
+    }
+
+    #[test]
+    fn field_codes_inside_words_are_filled_in() {
+        let path = Path::new("/apps/viewer.desktop");
+        let args = ["/tmp/a.png".to_owned(), "/tmp/b.png".to_owned()];
+        assert_eq!(
+            expand_field_codes("--open=%f", "Viewer", path, &args),
+            "--open=/tmp/a.png"
+        );
+        assert_eq!(
+            expand_field_codes("%c:%k", "Viewer", path, &args),
+            "Viewer:/apps/viewer.desktop"
+        );
+        assert_eq!(expand_field_codes("100%%", "Viewer", path, &[]), "100%");
+        assert_eq!(expand_field_codes("x%dy%m", "Viewer", path, &[]), "xy");
+    }
+
+    #[test]
+    fn command_line_expands_standalone_codes_and_drops_deprecated_ones() {
+        let entry = app("Icon=viewer\nExec=viewer %i %m --files %F %d%D --title=%c\n");
+        let path = Path::new("/apps/viewer.desktop");
+        let args = ["a".to_owned(), "b".to_owned()];
+        assert_eq!(
+            entry.command_line("Viewer", path, &args),
+            [
+                "viewer",
+                "--icon",
+                "viewer",
+                "--files",
+                "a",
+                "b",
+                "--title=Viewer"
+            ]
+        );
+        let entry = app("Exec=foo %m\n");
+        assert_eq!(entry.command_line("Foo", path, &[]), ["foo"]);
+        let entry = app("Exec=open %u\n");
+        assert_eq!(entry.command_line("Open", path, &args), ["open", "a"]);
+        assert_eq!(entry.command_line("Open", path, &[]), ["open"]);
+    }
+
+    #[test]
+    fn locale_names_go_from_most_to_least_specific() {
+        assert_eq!(
+            locale_names("sr_RS.UTF-8@latin"),
+            ["sr_RS@latin", "sr_RS", "sr@latin", "sr"]
+        );
+        assert_eq!(locale_names("de_DE.UTF-8"), ["de_DE", "de"]);
+        assert_eq!(locale_names("fr"), ["fr"]);
+        assert!(locale_names("C.UTF-8").is_empty());
+        assert!(locale_names("POSIX").is_empty());
+        assert!(locale_names("").is_empty());
+    }
+
+    #[test]
+    fn localized_values_prefer_the_most_specific_locale() {
+        let entry = app("Name[de]=Anwendung\nName[de_AT]=Programm\nComment=Plain\nComment[fr]=\n");
+        let austrian = environment(&["de_AT", "de"], &[]);
+        let german = environment(&["de_DE", "de"], &[]);
+        let french = environment(&["fr"], &[]);
+        assert_eq!(entry.localized("Name", &austrian).unwrap(), "Programm");
+        assert_eq!(entry.localized("Name", &german).unwrap(), "Anwendung");
+        assert_eq!(entry.localized("Name", &french).unwrap(), "App");
+        // An empty translation falls back to nothing rather than to the default
+        assert_eq!(entry.localized("Comment", &french), None);
+    }
+
+    #[test]
+    fn is_shown_follows_the_desktop_filters() {
+        let kde = environment(&[], &["KDE"]);
+        let gnome = environment(&[], &["GNOME"]);
+        assert!(app("").is_shown(&kde));
+        assert!(!app("Hidden=true\n").is_shown(&kde));
+        assert!(!app("NoDisplay=true\n").is_shown(&kde));
+        assert!(!app("Type=Link\n").is_shown(&kde));
+        assert!(app("OnlyShowIn=KDE;XFCE;\n").is_shown(&kde));
+        assert!(!app("OnlyShowIn=KDE;XFCE;\n").is_shown(&gnome));
+        assert!(!app("NotShowIn=GNOME;\n").is_shown(&gnome));
+        assert!(app("NotShowIn=GNOME;\n").is_shown(&kde));
+        assert!(
+            !DesktopEntry::parse("[Desktop Entry]\nType=Application\nName=App\n").is_shown(&kde)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn is_shown_needs_the_try_exec_program() {
+        let anywhere = environment(&[], &[]);
+        assert!(app("TryExec=sh\n").is_shown(&anywhere));
+        assert!(app("TryExec=/bin/sh\n").is_shown(&anywhere));
+        assert!(!app("TryExec=quick-launch-no-such-program\n").is_shown(&anywhere));
+        assert!(!app("TryExec=/nonexistent/program\n").is_shown(&anywhere));
+    }
+
+    #[test]
+    fn earlier_application_dirs_shadow_later_ones() {
+        let base =
+            std::env::temp_dir().join(format!("quick_launch-desktop-{}", std::process::id()));
+        let user = base.join("user");
+        let system = base.join("system");
+        let write = |dir: &Path, relative: &str, contents: &str| {
+            let path = dir.join(relative);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        };
+        let desktop = |name: &str, extra: &str| {
+            format!("[Desktop Entry]\nType=Application\nName={name}\nExec=app\n{extra}")
+        };
+        write(&user, "editor.desktop", &desktop("User editor", ""));
+        write(&system, "editor.desktop", &desktop("System editor", ""));
+        write(
+            &user,
+            "kde-viewer.desktop",
+            &desktop("Removed", "Hidden=true\n"),
+        );
+        write(&system, "kde/viewer.desktop", &desktop("Viewer", ""));
+        write(&system, "player.desktop", &desktop("Player", ""));
+
+        let apps = read_applications(&[user, system], &environment(&[], &[]));
+        let _ = std::fs::remove_dir_all(&base);
+
+        let listed: Vec<(&str, &str)> =
+            apps.iter().map(|app| (app.id.key(), app.label())).collect();
+        assert_eq!(
+            listed,
+            [
+                ("player.desktop", "Player"),
+                ("editor.desktop", "User editor")
+            ]
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+pub type AppResult<T> = Result<T, AppError>;
+
+/// Everything that can go wrong outside the launcher's control, e.g. a read-only config
+/// directory or a terminal that isn't installed
+#[derive(Debug)]
+pub enum AppError {
+    /// Writing one of the config files failed; `what` names the file's contents
+    SaveConfig {
+        what: &'static str,
+        source: confy::ConfyError,
+    },
+    /// Writing a file that isn't a confy config, e.g. the scan cache, failed
+    WriteFile { path: PathBuf, source: io::Error },
+    /// The script, or the terminal it should run in, could not be started
+    Launch { path: PathBuf, source: io::Error },
+    /// A file or folder could not be handed to the desktop or an editor
+    Open { path: PathBuf, source: io::Error },
+    /// `$VISUAL` or `$EDITOR` is not a valid command line
+    EditorCommand { command: String, reason: String },
+    /// A terminal profile whose argv template is empty
+    EmptyTerminalProfile { name: String },
+    /// The terminal profile's program is neither on `PATH` nor an existing file
+    TerminalNotFound { profile: String, program: String },
+}
+
+impl AppError {
+    /// The message followed by every underlying cause, one per line
+    pub fn details(&self) -> String {
+        let mut details = self.to_string();
+        let mut source = self.source();
+        while let Some(cause) = source {
+            details.push_str(&format!("\ncaused by: {cause}"));
+            source = cause.source();
+        }
+        details
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::SaveConfig { what, .. } => write!(f, "Failed to save {what}"),
+            AppError::WriteFile { path, .. } => write!(f, "Failed to write {}", path.display()),
+            AppError::Launch { path, .. } => write!(f, "Failed to launch {}", path.display()),
+            AppError::Open { path, .. } => write!(f, "Failed to open {}", path.display()),
+            AppError::EditorCommand { command, reason } => {
+                write!(f, "Invalid editor command {command:?}: {reason}")
+            }
+            AppError::EmptyTerminalProfile { name } => {
+                write!(f, "Terminal profile {name} has no program")
+            }
+            AppError::TerminalNotFound { profile, program } => {
+                write!(
+                    f,
+                    "Terminal {program} for profile {profile} is not installed"
+                )
+            }
+        }
+    }
+}
+
+impl Error for AppError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AppError::SaveConfig { source, .. } => Some(source),
+            AppError::WriteFile { source, .. }
+            | AppError::Launch { source, .. }
+            | AppError::Open { source, .. } => Some(source),
+            AppError::EditorCommand { .. }
+            | AppError::EmptyTerminalProfile { .. }
+            | AppError::TerminalNotFound { .. } => None,
+        }
+    }
+}
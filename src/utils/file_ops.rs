@@ -1,4 +1,5 @@
 use std::path::Path;
+use std::time::SystemTime;
 
 pub fn is_executable(path: &Path) -> bool {
     if !path.is_file() {
@@ -22,3 +23,10 @@ pub fn is_executable(path: &Path) -> bool {
             .unwrap_or(false)
     }
 }
+
+/// Modification time of the file or folder at `path`, following symlinks
+pub fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
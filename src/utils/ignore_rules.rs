@@ -0,0 +1,176 @@
+use super::file_ops::modified;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// Per-directory ignore file, read with gitignore syntax
+pub const IGNORE_FILE_NAME: &str = ".quicklaunchignore";
+
+/// Patterns ignored in every root unless the preferences say otherwise: dependency folders that
+/// ship their own executables, virtualenvs and editor backups. Only names no one gives a folder
+/// of their own scripts, so upgrading never hides scripts that used to be listed.
+pub fn default_ignore_patterns() -> Vec<String> {
+    [
+        "node_modules/",
+        "__pycache__/",
+        ".venv/",
+        "*~",
+        "*.bak",
+        "*.orig",
+        "*.swp",
+    ]
+    .into_iter()
+    .map(str::to_owned)
+    .collect()
+}
+
+/// The ignore rules in effect inside one directory: the global patterns, then every
+/// `.quicklaunchignore` from the root down to the directory
+#[derive(Clone)]
+pub struct IgnoreRules {
+    /// Outermost first; a later layer overrides an earlier one, as in git
+    layers: Vec<Arc<Gitignore>>,
+    /// Latest modification time of the ignore files read into `layers`
+    newest_file: Option<SystemTime>,
+}
+
+impl IgnoreRules {
+    /// Rules for `root` itself, with `patterns` anchored at it. Invalid patterns are skipped.
+    pub fn for_root(root: &Path, patterns: &[String]) -> Self {
+        let mut builder = GitignoreBuilder::new(root);
+        for pattern in patterns {
+            let _ = builder.add_line(None, pattern);
+        }
+        let global = builder.build().unwrap_or_else(|_| Gitignore::empty());
+        IgnoreRules {
+            layers: vec![Arc::new(global)],
+            newest_file: None,
+        }
+        .descend(root)
+    }
+
+    /// Rules for `dir`, a subdirectory of the one these rules apply to
+    pub fn descend(&self, dir: &Path) -> Self {
+        let ignore_file = dir.join(IGNORE_FILE_NAME);
+        if !ignore_file.is_file() {
+            return self.clone();
+        }
+        // Like git, keep the lines that parse even if others don't
+        let (gitignore, _) = Gitignore::new(&ignore_file);
+        let mut rules = self.clone();
+        rules.layers.push(Arc::new(gitignore));
+        rules.newest_file = rules.newest_file.max(modified(&ignore_file));
+        rules
+    }
+
+    /// When the most recently modified of the ignore files behind these rules was last changed
+    pub fn newest_file(&self) -> Option<SystemTime> {
+        self.newest_file
+    }
+
+    /// Whether `path`, an entry of the directory these rules apply to, should be left out
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        self.layers
+            .iter()
+            .rev()
+            .map(|layer| layer.matched(path, is_dir))
+            .find(|matched| !matched.is_none())
+            .is_some_and(|matched| matched.is_ignore())
+    }
+}
+
+/// Why a pattern can't be used, or `None` if it is valid gitignore syntax
+pub fn pattern_error(pattern: &str) -> Option<String> {
+    GitignoreBuilder::new("")
+        .add_line(None, pattern)
+        .err()
+        .map(|err| err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::temp_dir::TempDir;
+
+    fn patterns(patterns: &[&str]) -> Vec<String> {
+        patterns.iter().map(|pattern| pattern.to_string()).collect()
+    }
+
+    fn write_ignore_file(dir: &Path, contents: &str) {
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::write(dir.join(IGNORE_FILE_NAME), contents).unwrap();
+    }
+
+    #[test]
+    fn global_patterns_apply_in_the_root() {
+        let dir = TempDir::new("ignore-global");
+        let root = &dir.0;
+        let rules = IgnoreRules::for_root(root, &patterns(&["*.bak", "build/", "/top.sh"]));
+        assert!(rules.is_ignored(&root.join("script.bak"), false));
+        assert!(!rules.is_ignored(&root.join("script.sh"), false));
+        // A pattern ending in `/` only matches folders
+        assert!(rules.is_ignored(&root.join("build"), true));
+        assert!(!rules.is_ignored(&root.join("build"), false));
+        // Anchored patterns only match at the root itself
+        assert!(rules.is_ignored(&root.join("top.sh"), false));
+        let sub = rules.descend(&root.join("sub"));
+        assert!(!sub.is_ignored(&root.join("sub/top.sh"), false));
+        assert!(sub.is_ignored(&root.join("sub/old.bak"), true));
+    }
+
+    #[test]
+    fn ignore_files_override_the_global_patterns() {
+        let dir = TempDir::new("ignore-negation");
+        let root = &dir.0;
+        write_ignore_file(root, "!keep.bak\n*.sh\n");
+        let rules = IgnoreRules::for_root(root, &patterns(&["*.bak"]));
+        assert!(!rules.is_ignored(&root.join("keep.bak"), false));
+        assert!(rules.is_ignored(&root.join("other.bak"), false));
+        assert!(rules.is_ignored(&root.join("script.sh"), false));
+        assert!(rules.newest_file().is_some());
+    }
+
+    #[test]
+    fn nested_ignore_files_override_outer_ones_below_their_folder() {
+        let dir = TempDir::new("ignore-nested");
+        let root = &dir.0;
+        let sub = root.join("sub");
+        let deeper = sub.join("deeper");
+        write_ignore_file(root, "*.log\n");
+        write_ignore_file(&sub, "!important.log\nlocal.sh\n/anchored.sh\n");
+        std::fs::create_dir_all(&deeper).unwrap();
+
+        let root_rules = IgnoreRules::for_root(root, &[]);
+        let sub_rules = root_rules.descend(&sub);
+        let deeper_rules = sub_rules.descend(&deeper);
+
+        assert!(root_rules.is_ignored(&root.join("important.log"), false));
+        assert!(!root_rules.is_ignored(&root.join("local.sh"), false));
+
+        assert!(!sub_rules.is_ignored(&sub.join("important.log"), false));
+        assert!(sub_rules.is_ignored(&sub.join("other.log"), false));
+        assert!(sub_rules.is_ignored(&sub.join("local.sh"), false));
+        assert!(sub_rules.is_ignored(&sub.join("anchored.sh"), false));
+
+        // A folder without an ignore file inherits every layer above it
+        assert!(!deeper_rules.is_ignored(&deeper.join("important.log"), false));
+        assert!(deeper_rules.is_ignored(&deeper.join("local.sh"), false));
+        assert!(!deeper_rules.is_ignored(&deeper.join("anchored.sh"), false));
+    }
+
+    #[test]
+    fn no_ignore_files_means_only_the_global_patterns() {
+        let dir = TempDir::new("ignore-none");
+        let rules = IgnoreRules::for_root(&dir.0, &patterns(&["node_modules/"]));
+        assert!(rules.newest_file().is_none());
+        assert!(rules.is_ignored(&dir.0.join("node_modules"), true));
+        assert!(!rules.is_ignored(&dir.0.join("script.sh"), false));
+    }
+
+    #[test]
+    fn invalid_patterns_are_reported() {
+        assert!(pattern_error("*.sh").is_none());
+        assert!(pattern_error("[unclosed").is_some());
+    }
+}
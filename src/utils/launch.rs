@@ -1,67 +1,270 @@
+#[cfg(target_os = "macos")]
+use crate::utils::command_line::applescript_string;
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+use crate::utils::command_line::command_line;
+use crate::utils::command_line::{Shell, command_line_text, split_words};
+use crate::utils::error::{AppError, AppResult};
+use crate::utils::metadata::ScriptMeta;
 use crate::utils::task::Task;
+#[cfg(target_os = "linux")]
+use crate::utils::terminal::is_installed;
+use crate::utils::terminal::{KeepOpen, TerminalProfile};
 use rfd::FileDialog;
+use std::ffi::{OsStr, OsString};
 use std::io;
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::{Child, Command, Stdio};
 
-pub fn spawn_script_in_terminal(script_path: &Path) -> io::Result<()> {
+/// How a script gets started
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LaunchMode {
+    /// In a new terminal window, after which the launcher closes
+    Terminal,
+    /// Directly, with output shown in the launcher, which stays open
+    Background,
+    /// On its own, with no terminal and its output discarded, after which the launcher closes
+    Detached,
+}
+
+impl LaunchMode {
+    /// The mode `meta` asks for when nothing overrides it
+    pub fn declared_by(meta: &ScriptMeta) -> Self {
+        if meta.background {
+            LaunchMode::Background
+        } else if meta.detached {
+            LaunchMode::Detached
+        } else {
+            LaunchMode::Terminal
+        }
+    }
+}
+
+#[cfg(unix)]
+/// Wait for a single key press, falling back to a whole line where the tty can't be switched
+const WAIT_FOR_KEY: &str = "stty -icanon -echo 2>/dev/null; \
+    dd bs=1 count=1 >/dev/null 2>&1; stty icanon echo 2>/dev/null";
+
+#[cfg(unix)]
+/// POSIX `sh` appended to the script's command line to apply `keep_open`
+fn keep_open_suffix(keep_open: KeepOpen) -> String {
+    let report = r#"printf '\n[exited with status %s] Press any key to close' "$status""#;
+    match keep_open {
+        KeepOpen::Close => String::new(),
+        KeepOpen::PauseOnFailure => {
+            format!("; status=$?; if [ $status -ne 0 ]; then {report}; {WAIT_FOR_KEY}; fi")
+        }
+        KeepOpen::Pause => format!("; status=$?; {report}; {WAIT_FOR_KEY}"),
+        KeepOpen::Shell => "; exec \"${SHELL:-/bin/sh}\"".to_owned(),
+    }
+}
+
+/// A program to start, with its arguments and the folder to start it in
+#[derive(Debug, Clone)]
+pub struct CommandSpec {
+    pub program: OsString,
+    pub args: Vec<OsString>,
+    pub cwd: PathBuf,
+    /// Title for the terminal window
+    pub title: String,
+}
+
+impl CommandSpec {
+    /// Run the script at `script_path` from its own folder
+    pub fn script<S: AsRef<OsStr>>(script_path: &Path, args: &[S]) -> Self {
+        CommandSpec {
+            program: script_path.as_os_str().to_owned(),
+            args: args.iter().map(|arg| arg.as_ref().to_owned()).collect(),
+            cwd: script_path.parent().unwrap_or(Path::new(".")).to_path_buf(),
+            title: script_path
+                .file_name()
+                .unwrap_or(script_path.as_os_str())
+                .to_string_lossy()
+                .into_owned(),
+        }
+    }
+
+    /// The command as a command line for the platform's shell, e.g. for copying
+    pub fn command_line(&self) -> String {
+        command_line_text(Shell::native(), &self.program, &self.args)
+    }
+
+    fn launch_error(&self, source: io::Error) -> AppError {
+        AppError::Launch {
+            path: PathBuf::from(&self.program),
+            source,
+        }
+    }
+}
+
+/// Open a new terminal running `command`, using `profile` to start the emulator on Linux and
+/// `keep_open` to decide what happens once the command exits.
+pub fn spawn_in_terminal(
+    command: &CommandSpec,
+    profile: &TerminalProfile,
+    keep_open: KeepOpen,
+) -> AppResult<()> {
     #[cfg(target_os = "linux")]
     {
-        // Prefer whatever the user set; otherwise fall back.
-        let term = std::env::var("TERMINAL").unwrap_or_else(|_| "x-terminal-emulator".into());
+        let mut script = command_line(Shell::Posix, &command.program, &command.args);
+        script.push(keep_open_suffix(keep_open));
+        let cmd = [OsString::from("sh"), OsString::from("-c"), script];
+        let cmdline = command_line(Shell::Posix, &cmd[0], &cmd[1..]);
+        let argv = profile.expand(&cmd, &cmdline, &command.cwd, &command.title);
+        let Some(program) = argv.first().filter(|program| !program.is_empty()) else {
+            return Err(AppError::EmptyTerminalProfile {
+                name: profile.name.clone(),
+            });
+        };
+        // `setsid` detaches before running the terminal, so check for it up front or a missing
+        // one would fail silently
+        let program = program.to_string_lossy();
+        if !is_installed(&program) {
+            return Err(AppError::TerminalNotFound {
+                profile: profile.name.clone(),
+                program: program.into_owned(),
+            });
+        }
 
-        // Most modern emulators accept -e <cmd …>.
         Command::new("setsid")
-            .arg(&term)
-            .args([
-                "-e",
-                "bash",
-                "-c",
-                &format!("{path}; exec bash", path = script_path.to_string_lossy()),
-            ])
+            .args(&argv)
+            .current_dir(&command.cwd)
             .stdin(Stdio::null())
             .stdout(Stdio::null())
             .stderr(Stdio::null())
-            .spawn()?; // detached; don’t .wait()
+            .spawn() // detached; don’t .wait()
+            .map_err(|source| command.launch_error(source))?;
     }
+    #[cfg(not(target_os = "linux"))]
+    let _ = profile;
 
     #[cfg(target_os = "windows")]
     {
-        // “start” opens a new console window.
-        Command::new("cmd")
-            .args([
-                "/C",
-                "start",
-                "powershell",
-                "-NoExit", // keep window up
-                "-File",
-                script_path,
-            ])
-            .spawn()?;
+        use std::os::windows::process::CommandExt;
+        // Start PowerShell in a console of its own instead of going through `cmd /C start`,
+        // whose parsing would mangle paths containing `&`, `^` or `%`
+        const CREATE_NEW_CONSOLE: u32 = 0x0000_0010;
+        let mut powershell = Command::new("powershell");
+        powershell
+            .creation_flags(CREATE_NEW_CONSOLE)
+            .current_dir(&command.cwd);
+        if keep_open != KeepOpen::Close {
+            powershell.arg("-NoExit"); // keep window up
+        }
+        let is_script = Path::new(&command.program)
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("ps1"));
+        if is_script {
+            powershell
+                .arg("-File")
+                .arg(&command.program)
+                .args(&command.args);
+        } else {
+            powershell.arg("-Command").arg(command_line(
+                Shell::PowerShell,
+                &command.program,
+                &command.args,
+            ));
+        }
+        powershell
+            .spawn()
+            .map_err(|source| command.launch_error(source))?;
     }
 
     #[cfg(target_os = "macos")]
     {
-        // Ask Terminal.app (or iTerm) via AppleScript.
+        // Ask Terminal.app (or iTerm) via AppleScript, which only takes text
+        let cd = command_line_text(Shell::Posix, OsStr::new("cd"), &[&command.cwd]);
+        let mut script = command_line_text(Shell::Posix, &command.program, &command.args);
+        script = format!("{cd} && {script}");
+        script.push_str(&keep_open_suffix(keep_open));
+        let line = command_line_text(Shell::Posix, OsStr::new("sh"), &["-c", script.as_str()]);
         Command::new("osascript")
             .args([
                 "-e",
                 &format!(
-                    "tell application \"Terminal\" to do script \"bash -c '{}; exec bash'\"",
-                    script_path.replace('\'', "'\\''") // escape single quotes
+                    "tell application \"Terminal\" to do script {}",
+                    applescript_string(&line)
                 ),
             ])
-            .spawn()?;
+            .spawn()
+            .map_err(|source| command.launch_error(source))?;
     }
 
     Ok(())
 }
 
-pub fn open_native_file_viewer(path: &Path) -> io::Result<()> {
-    open::that(path)
+/// Start `command` on its own, with no terminal and its output discarded, so it keeps running
+/// after the launcher exits
+pub fn spawn_detached(command: &CommandSpec) -> AppResult<()> {
+    let mut process = Command::new(&command.program);
+    process
+        .args(&command.args)
+        .current_dir(&command.cwd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    // Out of the launcher's process group, so Ctrl-C in the terminal it was started from spares it
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut process, 0);
+    let mut child = process
+        .spawn()
+        .map_err(|source| command.launch_error(source))?;
+    // Reap it once it exits, so it doesn't linger as a zombie while the launcher stays open
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// Start `command` in the current terminal, as the command line does for background scripts;
+/// the caller waits for it
+pub fn spawn_attached(command: &CommandSpec) -> AppResult<Child> {
+    Command::new(&command.program)
+        .args(&command.args)
+        .current_dir(&command.cwd)
+        .spawn()
+        .map_err(|source| command.launch_error(source))
+}
+
+/// Open `path` in `$VISUAL` or `$EDITOR` in a new terminal, or in the desktop's default
+/// application when neither is set
+pub fn open_in_editor(path: &Path, profile: &TerminalProfile) -> AppResult<()> {
+    let editor = ["VISUAL", "EDITOR"]
+        .into_iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|editor| !editor.trim().is_empty());
+    let Some(editor) = editor else {
+        return open::that_detached(path).map_err(|source| AppError::Open {
+            path: path.to_path_buf(),
+            source,
+        });
+    };
+    let words = split_words(&editor).map_err(|reason| AppError::EditorCommand {
+        command: editor.clone(),
+        reason,
+    })?;
+    let mut args: Vec<OsString> = words.iter().skip(1).map(OsString::from).collect();
+    args.push(path.as_os_str().to_owned());
+    let command = CommandSpec {
+        program: OsString::from(&words[0]),
+        args,
+        cwd: path.parent().unwrap_or(Path::new(".")).to_path_buf(),
+        title: editor,
+    };
+    spawn_in_terminal(&command, profile, KeepOpen::Close)
+}
+
+pub fn open_native_file_viewer(path: &Path) -> AppResult<()> {
+    open::that(path).map_err(|source| AppError::Open {
+        path: path.to_path_buf(),
+        source,
+    })
 }
 
 /// Kick off a folder dialog in a background thread.
 pub fn pick_folder_async() -> Task<Option<PathBuf>> {
     Task::spawn(|| FileDialog::new().pick_folder()) // blocking call in here
 }
+
+/// Kick off a file dialog in a background thread.
+pub fn pick_file_async() -> Task<Option<PathBuf>> {
+    Task::spawn(|| FileDialog::new().pick_file())
+}
@@ -0,0 +1,126 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use super::script_args::ScriptArg;
+use super::terminal::KeepOpen;
+use serde::{Deserialize, Serialize};
+
+/// Marker that introduces a directive inside a header comment, e.g. `# ql:name=Backup`
+const DIRECTIVE_PREFIX: &str = "ql:";
+/// Only the top of a script is inspected; a header further down is not a header
+const MAX_HEADER_BYTES: u64 = 8 * 1024;
+const COMMENT_PREFIXES: [&str; 4] = ["#", "//", "--", "::"];
+
+/// Optional details a script can declare about itself in its header comments
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScriptMeta {
+    /// Friendly name shown instead of the file name
+    pub name: Option<String>,
+    pub description: Option<String>,
+    /// Nerd Font glyph shown in front of the name
+    pub icon: Option<char>,
+    pub tags: Vec<String>,
+    /// Ask before launching
+    pub confirm: bool,
+    /// Run without a terminal, collecting output in the launcher
+    pub background: bool,
+    /// Start on its own, without a terminal or collected output, as for a desktop application
+    #[serde(default)]
+    pub detached: bool,
+    /// Terminal profile to launch in instead of the configured one
+    pub terminal: Option<String>,
+    /// What the terminal does once the script exits, instead of the configured policy
+    pub keep_open: Option<KeepOpen>,
+    /// Parameters to prompt for before launching, in argv order
+    pub args: Vec<ScriptArg>,
+}
+
+/// Read the header block of the script at `path`.
+///
+/// Unreadable and binary files yield empty metadata.
+pub fn read_script_meta(path: &Path) -> ScriptMeta {
+    let mut buf = Vec::new();
+    let read = File::open(path).and_then(|file| file.take(MAX_HEADER_BYTES).read_to_end(&mut buf));
+    match read {
+        Ok(_) => parse_header(&String::from_utf8_lossy(&buf)),
+        Err(_) => ScriptMeta::default(),
+    }
+}
+
+/// Collect `ql:` directives from the leading comment lines of `text`.
+///
+/// The shebang and blank lines are skipped; the first line that is not a comment ends the header.
+pub fn parse_header(text: &str) -> ScriptMeta {
+    let mut meta = ScriptMeta::default();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("#!") {
+            continue;
+        }
+        let Some(comment) = strip_comment(line) else {
+            break;
+        };
+        let Some(directive) = comment.trim_start().strip_prefix(DIRECTIVE_PREFIX) else {
+            continue;
+        };
+        let (key, value) = match directive.split_once('=') {
+            Some((key, value)) => (key.trim(), Some(value.trim())),
+            None => (directive.trim(), None),
+        };
+
+        match (key, value) {
+            ("name", Some(value)) if !value.is_empty() => meta.name = Some(value.to_owned()),
+            ("description", Some(value)) if !value.is_empty() => {
+                meta.description = Some(value.to_owned())
+            }
+            ("icon", Some(value)) => meta.icon = parse_icon(value),
+            ("tags", Some(value)) => {
+                meta.tags = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|tag| !tag.is_empty())
+                    .map(str::to_owned)
+                    .collect()
+            }
+            ("confirm", None) => meta.confirm = true,
+            ("confirm", Some(value)) => meta.confirm = parse_flag(value),
+            ("background", None) => meta.background = true,
+            ("background", Some(value)) => meta.background = parse_flag(value),
+            ("terminal", Some(value)) if !value.is_empty() => {
+                meta.terminal = Some(value.to_owned())
+            }
+            ("keep_open", Some(value)) => meta.keep_open = KeepOpen::parse(value),
+            ("arg", Some(value)) => meta.args.extend(ScriptArg::parse(value)),
+            _ => {}
+        }
+    }
+    meta
+}
+
+fn strip_comment(line: &str) -> Option<&str> {
+    COMMENT_PREFIXES
+        .iter()
+        .find_map(|prefix| line.strip_prefix(prefix))
+}
+
+fn parse_flag(value: &str) -> bool {
+    matches!(
+        value.to_ascii_lowercase().as_str(),
+        "" | "1" | "true" | "yes" | "on"
+    )
+}
+
+/// Accept either the glyph itself or its codepoint, written as `f120`, `U+F120`, `\uf120` or
+/// `0xf120`
+fn parse_icon(value: &str) -> Option<char> {
+    let mut chars = value.chars();
+    if let (Some(glyph), None) = (chars.next(), chars.next()) {
+        return Some(glyph);
+    }
+    let hex = ["U+", "u+", "\\u", "0x"]
+        .iter()
+        .find_map(|prefix| value.strip_prefix(prefix))
+        .unwrap_or(value);
+    u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+}
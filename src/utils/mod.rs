@@ -1,6 +1,20 @@
 pub(crate) mod config;
 
+pub(crate) mod background;
 pub(crate) mod build_tree;
+pub(crate) mod command_line;
+pub(crate) mod desktop_apps;
+pub(crate) mod error;
 pub mod file_ops;
+pub(crate) mod ignore_rules;
 pub(crate) mod launch;
+pub(crate) mod metadata;
+pub(crate) mod provider;
+pub(crate) mod script_args;
+pub(crate) mod search;
 pub(crate) mod task;
+pub(crate) mod task_files;
+#[cfg(test)]
+pub(crate) mod temp_dir;
+pub(crate) mod terminal;
+pub(crate) mod watch;
@@ -0,0 +1,198 @@
+use super::build_tree::{Entry, file_name_string};
+use super::desktop_apps::DesktopApps;
+use super::file_ops::{is_executable, modified};
+use super::launch::CommandSpec;
+use super::metadata::read_script_meta;
+use super::task_files::TaskRunner;
+use crate::preferences::{AppPreferences, ScriptRoot};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+/// Name of the provider that lists executable files as scripts
+pub const SCRIPT_PROVIDER: &str = "script";
+
+/// Every provider, in the order they are asked about a file
+static PROVIDERS: [&dyn Provider; 6] = [
+    &TaskRunner::CargoMake,
+    &TaskRunner::Make,
+    &TaskRunner::Just,
+    &TaskRunner::Npm,
+    &Scripts,
+    &DesktopApps,
+];
+
+/// Names an entry across scans and runs, for usage stats, favorites and the like: the name of the
+/// provider that yields the entry, a colon, and a key unique within that provider, e.g.
+/// `script:/home/me/bin/backup.sh` or `just:/home/me/project/justfile#build`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(from = "String", into = "String")]
+pub struct EntryId(String);
+
+impl EntryId {
+    pub fn new(provider: &str, key: &str) -> Self {
+        EntryId(format!("{provider}:{key}"))
+    }
+
+    /// The id of the script at `path`
+    pub fn script(path: &Path) -> Self {
+        EntryId::new(SCRIPT_PROVIDER, &path.to_string_lossy())
+    }
+
+    /// Name of the provider the entry comes from
+    pub fn provider(&self) -> &str {
+        self.0.split_once(':').map_or("", |(provider, _)| provider)
+    }
+
+    /// The part after the provider name; for entries read from a file it starts with its path
+    pub fn key(&self) -> &str {
+        self.0.split_once(':').map_or(&self.0, |(_, key)| key)
+    }
+}
+
+impl From<String> for EntryId {
+    /// Ids saved before there were providers are plain script paths
+    fn from(id: String) -> Self {
+        let qualified = id
+            .split_once(':')
+            .is_some_and(|(name, _)| provider(name).is_some());
+        if qualified {
+            EntryId(id)
+        } else {
+            EntryId::new(SCRIPT_PROVIDER, &id)
+        }
+    }
+}
+
+impl From<EntryId> for String {
+    fn from(id: EntryId) -> Self {
+        id.0
+    }
+}
+
+impl fmt::Display for EntryId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A source of launchable entries. While a script root is scanned, every file found is offered to
+/// the enabled providers in turn until one of them reads it. A provider can also find entries by
+/// itself, which are then listed in a section of their own.
+pub trait Provider: Sync {
+    /// Prefix of the ids of the provider's entries; stored ids depend on it never changing
+    fn name(&self) -> &'static str;
+
+    /// The entries the file at `path` defines, or `None` if the provider doesn't read such files
+    fn read_file(&self, path: &Path) -> Option<Vec<Entry>>;
+
+    /// The command launching `entry` with `args` passed on to it
+    fn command(&self, entry: &Entry, args: &[String]) -> CommandSpec;
+
+    /// Entries found other than by reading files under the script roots, such as installed
+    /// applications; `None` if the provider finds none that way or `preferences` turn it off
+    fn discover(&self, _preferences: &AppPreferences) -> Option<Vec<Entry>> {
+        None
+    }
+
+    /// Header of the section listing the entries from [`Provider::discover`]
+    fn section_label(&self) -> &'static str {
+        self.name()
+    }
+
+    /// Whether the launch history of `id`, one of the provider's entries, is kept once the
+    /// script roots are `roots`. By default it is while the file the entry was read from, whose
+    /// path starts the id's key, lies under one of them.
+    fn keeps_history(&self, id: &EntryId, roots: &[ScriptRoot]) -> bool {
+        let path = Path::new(id.key());
+        roots.iter().any(|root| path.starts_with(&root.path))
+    }
+
+    /// Whether `entry`, read in an earlier scan, still holds without reading its file again
+    fn is_current(&self, entry: &Entry) -> bool {
+        entry.file_unchanged()
+    }
+}
+
+/// The provider called `name`
+pub fn provider(name: &str) -> Option<&'static dyn Provider> {
+    PROVIDERS
+        .iter()
+        .copied()
+        .find(|provider| provider.name() == name)
+}
+
+/// Entries one provider found by itself, shown under a header of their own
+pub struct ProviderSection {
+    pub label: &'static str,
+    pub entries: Vec<Entry>,
+}
+
+/// What every provider finds by itself with `preferences`, one section per provider that finds
+/// anything, in provider order
+pub fn discover(preferences: &AppPreferences) -> Vec<ProviderSection> {
+    PROVIDERS
+        .iter()
+        .filter_map(|provider| {
+            let entries = provider.discover(preferences)?;
+            (!entries.is_empty()).then(|| ProviderSection {
+                label: provider.section_label(),
+                entries,
+            })
+        })
+        .collect()
+}
+
+/// The entry called `id` among the `sections` providers found by themselves
+pub fn find_discovered<'a>(sections: &'a [ProviderSection], id: &EntryId) -> Option<&'a Entry> {
+    sections
+        .iter()
+        .flat_map(|section| &section.entries)
+        .find(|entry| entry.id == *id)
+}
+
+/// The providers reading files while scanning, in the order they are asked: the task runners in
+/// `task_runners`, then the script provider, so an executable Makefile still lists its targets
+pub fn file_providers(task_runners: &[TaskRunner]) -> Vec<&'static dyn Provider> {
+    PROVIDERS
+        .iter()
+        .copied()
+        .filter(|provider| {
+            provider.name() == SCRIPT_PROVIDER
+                || task_runners
+                    .iter()
+                    .any(|runner| runner.name() == provider.name())
+        })
+        .collect()
+}
+
+/// Executable files, with the details declared in their header comments
+struct Scripts;
+
+impl Provider for Scripts {
+    fn name(&self) -> &'static str {
+        SCRIPT_PROVIDER
+    }
+
+    fn read_file(&self, path: &Path) -> Option<Vec<Entry>> {
+        if !is_executable(path) {
+            return None;
+        }
+        Some(vec![Entry {
+            id: EntryId::script(path),
+            name: file_name_string(path),
+            path: path.to_path_buf(),
+            meta: read_script_meta(path),
+            mtime: modified(path),
+        }])
+    }
+
+    fn command(&self, entry: &Entry, args: &[String]) -> CommandSpec {
+        CommandSpec::script(&entry.path, args)
+    }
+
+    /// Changing the mode leaves the modification time alone, so check it is still executable
+    fn is_current(&self, entry: &Entry) -> bool {
+        entry.file_unchanged() && is_executable(&entry.path)
+    }
+}
@@ -0,0 +1,264 @@
+use super::command_line::split_words;
+use serde::{Deserialize, Serialize};
+
+/// Kind of value a script parameter accepts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArgKind {
+    String,
+    Int,
+    Bool,
+    Choice(Vec<String>),
+    Path,
+    /// Free text split into separate arguments the way a shell would
+    Words,
+}
+
+/// A parameter declared in a script header with `ql:arg=`
+///
+/// The directive value is `name|type|default|help`, where everything after the name is optional
+/// and a choice is written as `choice(a,b,c)`:
+///
+/// ```text
+/// # ql:arg=env|choice(dev,prod)|dev|Target environment
+/// # ql:arg=--verbose|bool|false|Print every step
+/// ```
+///
+/// Names starting with `-` are passed as options (`--name value`, or just `--name` for a set
+/// bool); all others are passed positionally in declaration order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptArg {
+    pub name: String,
+    pub kind: ArgKind,
+    pub default: String,
+    pub help: Option<String>,
+}
+
+impl ScriptArg {
+    /// The parameter declared by a `ql:arg=` value, or `None` if it has no name, or is a choice
+    /// without choices or with a default that isn't one of them, as the form could never be
+    /// submitted
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.splitn(4, '|').map(str::trim);
+        let name = parts.next().filter(|name| !name.is_empty())?.to_owned();
+        let kind = match parts.next().unwrap_or("string") {
+            "" | "string" | "str" => ArgKind::String,
+            "int" | "integer" => ArgKind::Int,
+            "bool" | "flag" => ArgKind::Bool,
+            "path" | "file" => ArgKind::Path,
+            "words" => ArgKind::Words,
+            other => {
+                let choices = other.strip_prefix("choice(")?.strip_suffix(')')?;
+                ArgKind::Choice(
+                    choices
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|choice| !choice.is_empty())
+                        .map(str::to_owned)
+                        .collect(),
+                )
+            }
+        };
+        let default = match (parts.next().unwrap_or(""), &kind) {
+            ("", ArgKind::Bool) => "false".to_owned(),
+            ("", ArgKind::Choice(choices)) => choices.first().cloned().unwrap_or_default(),
+            (default, _) => default.to_owned(),
+        };
+        if let ArgKind::Choice(choices) = &kind
+            && !choices.contains(&default)
+        {
+            return None;
+        }
+        let help = parts
+            .next()
+            .filter(|help| !help.is_empty())
+            .map(str::to_owned);
+        Some(ScriptArg {
+            name,
+            kind,
+            default,
+            help,
+        })
+    }
+
+    pub fn is_option(&self) -> bool {
+        self.name.starts_with('-')
+    }
+
+    /// Check a value entered in the form, describing the problem if it is not acceptable
+    pub fn validate(&self, value: &str) -> Result<(), String> {
+        match &self.kind {
+            ArgKind::String => Ok(()),
+            ArgKind::Int => value
+                .trim()
+                .parse::<i64>()
+                .map(|_| ())
+                .map_err(|_| "must be a whole number".to_owned()),
+            ArgKind::Bool => match value {
+                "true" | "false" => Ok(()),
+                _ => Err("must be true or false".to_owned()),
+            },
+            ArgKind::Choice(choices) => {
+                if choices.iter().any(|choice| choice == value) {
+                    Ok(())
+                } else {
+                    Err(format!("must be one of {}", choices.join(", ")))
+                }
+            }
+            ArgKind::Path => {
+                if value.trim().is_empty() {
+                    Err("a path is required".to_owned())
+                } else {
+                    Ok(())
+                }
+            }
+            ArgKind::Words => split_words(value).map(|_| ()),
+        }
+    }
+}
+
+/// Turn validated form values into the argv passed to the script
+pub fn build_argv(args: &[ScriptArg], values: &[String]) -> Vec<String> {
+    let mut argv = Vec::new();
+    for (arg, value) in args.iter().zip(values) {
+        let value = match arg.kind {
+            ArgKind::Int | ArgKind::Path => value.trim(),
+            _ => value.as_str(),
+        };
+        match (&arg.kind, arg.is_option()) {
+            (ArgKind::Words, is_option) => {
+                let words = split_words(value).unwrap_or_default();
+                if is_option && !words.is_empty() {
+                    argv.push(arg.name.clone());
+                }
+                argv.extend(words);
+            }
+            (ArgKind::Bool, true) => {
+                if value == "true" {
+                    argv.push(arg.name.clone());
+                }
+            }
+            (_, true) => {
+                argv.push(arg.name.clone());
+                argv.push(value.to_owned());
+            }
+            (_, false) => argv.push(value.to_owned()),
+        }
+    }
+    argv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arg(value: &str) -> ScriptArg {
+        ScriptArg::parse(value).unwrap()
+    }
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_reads_every_field() {
+        let env = arg("env | choice(dev, prod) | prod | Target environment");
+        assert_eq!(env.name, "env");
+        assert_eq!(env.kind, ArgKind::Choice(strings(&["dev", "prod"])));
+        assert_eq!(env.default, "prod");
+        assert_eq!(env.help.as_deref(), Some("Target environment"));
+
+        let help = arg("message|string||Says what | and why");
+        assert_eq!(help.help.as_deref(), Some("Says what | and why"));
+    }
+
+    #[test]
+    fn parse_fills_in_defaults() {
+        let plain = arg("name");
+        assert_eq!(plain.kind, ArgKind::String);
+        assert_eq!(plain.default, "");
+        assert_eq!(plain.help, None);
+        assert_eq!(arg("--verbose|flag").default, "false");
+        assert_eq!(arg("env|choice(dev,prod)").default, "dev");
+        assert_eq!(arg("count|integer|3").kind, ArgKind::Int);
+        assert_eq!(arg("input|file").kind, ArgKind::Path);
+        assert_eq!(arg("rest|words").kind, ArgKind::Words);
+    }
+
+    #[test]
+    fn parse_rejects_unusable_declarations() {
+        assert!(ScriptArg::parse("").is_none());
+        assert!(ScriptArg::parse(" |int").is_none());
+        assert!(ScriptArg::parse("x|number").is_none());
+        assert!(ScriptArg::parse("x|choice(a,b").is_none());
+        assert!(ScriptArg::parse("x|choice()").is_none());
+        assert!(ScriptArg::parse("x|choice(,)").is_none());
+        assert!(ScriptArg::parse("x|choice(a,b)|c").is_none());
+    }
+
+    #[test]
+    fn validate_checks_the_kind() {
+        assert!(arg("n|int").validate(" -42 ").is_ok());
+        assert!(arg("n|int").validate("4.2").is_err());
+        assert!(arg("b|bool").validate("true").is_ok());
+        assert!(arg("b|bool").validate("yes").is_err());
+        assert!(arg("c|choice(a,b)").validate("b").is_ok());
+        assert_eq!(
+            arg("c|choice(a,b)").validate("c"),
+            Err("must be one of a, b".to_owned())
+        );
+        assert!(arg("p|path").validate("  ").is_err());
+        assert!(arg("w|words").validate("a 'b c'").is_ok());
+        assert!(arg("w|words").validate("'open").is_err());
+        assert!(arg("s").validate("").is_ok());
+    }
+
+    #[test]
+    fn every_declared_default_passes_validation() {
+        for value in ["s", "n|int|7", "b|bool", "c|choice(a,b)", "w|words|x y"] {
+            let arg = arg(value);
+            assert!(arg.validate(&arg.default).is_ok(), "{value}");
+        }
+    }
+
+    #[test]
+    fn build_argv_places_options_and_positionals() {
+        let args = [
+            arg("target"),
+            arg("--count|int"),
+            arg("--verbose|bool"),
+            arg("--quiet|bool"),
+            arg("files|words"),
+            arg("--tags|words"),
+            arg("--out|path"),
+        ];
+        let values = strings(&[
+            "build dir",
+            " 3 ",
+            "true",
+            "false",
+            "a.txt 'b c.txt'",
+            "",
+            " out.log ",
+        ]);
+        assert_eq!(
+            build_argv(&args, &values),
+            [
+                "build dir",
+                "--count",
+                "3",
+                "--verbose",
+                "a.txt",
+                "b c.txt",
+                "--out",
+                "out.log"
+            ]
+        );
+    }
+
+    #[test]
+    fn build_argv_names_word_options_once() {
+        let args = [arg("--tag|words")];
+        assert_eq!(build_argv(&args, &strings(&["x y"])), ["--tag", "x", "y"]);
+    }
+}
@@ -0,0 +1,87 @@
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use std::path::Path;
+
+use super::build_tree::RootFolder;
+use super::metadata::ScriptMeta;
+use super::provider::{EntryId, ProviderSection};
+
+/// How much launch history counts relative to the fuzzy score when ranking hits
+const USAGE_WEIGHT: f64 = 16.0;
+
+/// A single entry that matched the search query
+pub struct SearchHit<'a> {
+    pub label: &'a str,
+    pub id: &'a EntryId,
+    /// The file the entry was read from
+    pub path: &'a Path,
+    pub meta: &'a ScriptMeta,
+    /// Char indices of `label` that matched the query, used for highlighting
+    pub indices: Vec<usize>,
+    pub rank: f64,
+}
+
+/// Fuzzy-match every entry in `roots` and in the `discovered` sections against `query`, best hits
+/// first.
+///
+/// Labels are matched first so their characters can be highlighted; the full path and the header
+/// tags are matched as a fallback so typing a folder name or tag still finds the scripts.
+pub fn search<'a>(
+    roots: &'a [RootFolder],
+    discovered: &'a [ProviderSection],
+    query: &str,
+    usage: &dyn Fn(&EntryId) -> f64,
+) -> Vec<SearchHit<'a>> {
+    let matcher = SkimMatcherV2::default().smart_case();
+    let candidates = roots.iter().flat_map(|root| {
+        root.entries
+            .iter()
+            .map(|entry| (entry.label(), &entry.id, entry.path.as_path(), &entry.meta))
+            .chain(root.folders.iter().flat_map(|folder| {
+                folder.flat_entries.iter().map(|entry| {
+                    (
+                        entry.display_name.as_str(),
+                        &entry.id,
+                        entry.path.as_path(),
+                        &entry.meta,
+                    )
+                })
+            }))
+    });
+    let candidates = candidates.chain(
+        discovered
+            .iter()
+            .flat_map(|section| &section.entries)
+            .map(|entry| (entry.label(), &entry.id, entry.path.as_path(), &entry.meta)),
+    );
+
+    let mut hits: Vec<SearchHit> = candidates
+        .filter_map(|(label, id, path, meta)| {
+            let label_match = matcher.fuzzy_indices(label, query);
+            let path_score = std::iter::once(path.to_string_lossy())
+                .chain(meta.tags.iter().map(|tag| tag.into()))
+                .filter_map(|haystack| matcher.fuzzy_match(&haystack, query))
+                .max();
+            let (score, indices) = match (label_match, path_score) {
+                (Some((label_score, indices)), Some(path_score)) if path_score > label_score => {
+                    (path_score, indices)
+                }
+                (Some((label_score, indices)), _) => (label_score, indices),
+                (None, Some(path_score)) => (path_score, Vec::new()),
+                (None, None) => return None,
+            };
+            let rank = score as f64 + USAGE_WEIGHT * usage(id).ln_1p();
+            Some(SearchHit {
+                label,
+                id,
+                path,
+                meta,
+                indices,
+                rank,
+            })
+        })
+        .collect();
+
+    hits.sort_by(|a, b| b.rank.total_cmp(&a.rank).then_with(|| a.label.cmp(b.label)));
+    hits
+}
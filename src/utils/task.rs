@@ -1,5 +1,9 @@
 use std::{
-    sync::mpsc::{self, Receiver},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+    },
     thread::{self, JoinHandle},
 };
 
@@ -35,3 +39,92 @@ impl<T> Task<T> {
         }
     }
 }
+
+/// Handle through which the work of a [`StreamTask`] hands over partial results and learns
+/// that it was cancelled.
+pub struct Progress<P> {
+    tx: Sender<P>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl<P> Progress<P> {
+    /// Hand over a partial result; dropped silently if the UI no longer cares.
+    pub fn send(&self, part: P) {
+        let _ = self.tx.send(part);
+    }
+
+    /// Set once the task is cancelled or dropped; long-running work should check it and stop.
+    pub fn cancelled(&self) -> &AtomicBool {
+        &self.cancelled
+    }
+}
+
+/// Background work that produces its result in parts of type `P`. Dropping the handle cancels
+/// the work.
+pub struct StreamTask<P> {
+    rx: Receiver<P>,
+    cancelled: Arc<AtomicBool>,
+    finished: bool,
+    _jh: JoinHandle<()>,
+}
+
+impl<P> StreamTask<P> {
+    /// Spawn `work` on a background thread; it reports parts through the given [`Progress`].
+    pub fn spawn<F>(work: F) -> Self
+    where
+        F: FnOnce(&Progress<P>) + Send + 'static,
+        P: Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let progress = Progress {
+            tx,
+            cancelled: Arc::clone(&cancelled),
+        };
+        let jh = thread::spawn(move || work(&progress));
+        Self {
+            rx,
+            cancelled,
+            finished: false,
+            _jh: jh,
+        }
+    }
+
+    /// Non-blocking; every part sent since the last call, oldest first.
+    pub fn try_parts(&mut self) -> Vec<P> {
+        use std::sync::mpsc::TryRecvError::*;
+        let mut parts = Vec::new();
+        loop {
+            match self.rx.try_recv() {
+                Ok(part) => parts.push(part),
+                Err(Empty) => break,
+                Err(Disconnected) => {
+                    self.finished = true;
+                    break;
+                }
+            }
+        }
+        parts
+    }
+
+    /// Whether the work has returned and all of its parts have been taken.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Ask the work to stop; parts it already sent can still be taken.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether [`cancel`](Self::cancel) was called, so the parts may not be the whole result.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+impl<P> Drop for StreamTask<P> {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
@@ -0,0 +1,523 @@
+use super::build_tree::Entry;
+use super::command_line::split_words;
+use super::file_ops::modified;
+use super::launch::CommandSpec;
+use super::metadata::ScriptMeta;
+use super::provider::{EntryId, Provider};
+use super::script_args::{ArgKind, ScriptArg};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::path::Path;
+
+/// A tool that runs named tasks defined in a file, such as a Makefile
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TaskRunner {
+    CargoMake,
+    Make,
+    Just,
+    Npm,
+}
+
+impl TaskRunner {
+    pub const ALL: [TaskRunner; 4] = [
+        TaskRunner::CargoMake,
+        TaskRunner::Make,
+        TaskRunner::Just,
+        TaskRunner::Npm,
+    ];
+
+    /// Names of the files the tool reads its tasks from, in the order it looks for them
+    pub fn file_names(self) -> &'static [&'static str] {
+        match self {
+            TaskRunner::CargoMake => &["Makefile.toml"],
+            TaskRunner::Make => &["GNUmakefile", "makefile", "Makefile"],
+            TaskRunner::Just => &["justfile", "Justfile", ".justfile"],
+            TaskRunner::Npm => &["package.json"],
+        }
+    }
+
+    /// The words that run a task, e.g. `npm run`
+    pub fn prefix(self) -> &'static [&'static str] {
+        match self {
+            TaskRunner::CargoMake => &["cargo", "make"],
+            TaskRunner::Make => &["make"],
+            TaskRunner::Just => &["just"],
+            TaskRunner::Npm => &["npm", "run"],
+        }
+    }
+
+    /// Tasks defined in `text`, the contents of one of the runner's files, in file order.
+    /// Private tasks and npm lifecycle hooks are left out.
+    fn parse(self, text: &str) -> Vec<TaskDef> {
+        match self {
+            TaskRunner::CargoMake => cargo_make_tasks(text),
+            TaskRunner::Make => make_targets(text),
+            TaskRunner::Just => just_recipes(text),
+            TaskRunner::Npm => npm_scripts(text),
+        }
+    }
+
+    /// Whether the file at `path` sits next to one the tool looks for first, so the tool never
+    /// reads it. Goes by the names actually listed, as on a case-insensitive file system
+    /// `makefile` also opens `Makefile`.
+    fn is_shadowed(self, path: &Path) -> bool {
+        let names = self.file_names();
+        let Some(position) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| names.iter().position(|candidate| *candidate == name))
+        else {
+            return false;
+        };
+        let earlier = &names[..position];
+        if earlier.is_empty() {
+            return false;
+        }
+        let Some(dir) = path.parent() else {
+            return false;
+        };
+        std::fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .any(|dir_entry| {
+                dir_entry
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|name| earlier.contains(&name))
+            })
+    }
+}
+
+impl Provider for TaskRunner {
+    fn name(&self) -> &'static str {
+        match self {
+            TaskRunner::CargoMake => "cargo-make",
+            TaskRunner::Make => "make",
+            TaskRunner::Just => "just",
+            TaskRunner::Npm => "npm",
+        }
+    }
+
+    /// One entry per task, named after the command line running it, e.g. `make build`. A file
+    /// the tool passes over for another one in the same folder is claimed but yields nothing.
+    fn read_file(&self, path: &Path) -> Option<Vec<Entry>> {
+        let name = path.file_name()?.to_str()?;
+        if !self.file_names().contains(&name) {
+            return None;
+        }
+        if self.is_shadowed(path) {
+            return Some(Vec::new());
+        }
+        let mtime = modified(path);
+        let key_prefix = format!("{}#", path.to_string_lossy());
+        let entries = read_tasks(*self, path)
+            .into_iter()
+            .map(|task| {
+                let label = [self.prefix(), &[task.name.as_str()]].concat().join(" ");
+                Entry {
+                    id: EntryId::new(self.name(), &format!("{key_prefix}{}", task.name)),
+                    meta: ScriptMeta {
+                        name: Some(label),
+                        description: task.description,
+                        args: task.args,
+                        ..ScriptMeta::default()
+                    },
+                    name: task.name,
+                    path: path.to_path_buf(),
+                    mtime,
+                }
+            })
+            .collect();
+        Some(entries)
+    }
+
+    /// Runs in the task file's folder; npm needs `--` before arguments meant for the script
+    fn command(&self, entry: &Entry, args: &[String]) -> CommandSpec {
+        let (program, prefix) = self
+            .prefix()
+            .split_first()
+            .expect("every runner has a command");
+        let mut command_args: Vec<OsString> = prefix.iter().map(OsString::from).collect();
+        command_args.push(OsString::from(&entry.name));
+        if *self == TaskRunner::Npm && !args.is_empty() {
+            command_args.push(OsString::from("--"));
+        }
+        command_args.extend(args.iter().map(OsString::from));
+        CommandSpec {
+            program: OsString::from(program),
+            args: command_args,
+            cwd: entry.path.parent().unwrap_or(Path::new(".")).to_path_buf(),
+            title: entry.label().to_owned(),
+        }
+    }
+}
+
+/// A task as read from its file
+struct TaskDef {
+    name: String,
+    description: Option<String>,
+    /// Parameters the task takes, for the argument form
+    args: Vec<ScriptArg>,
+}
+
+/// Tasks in the `runner` file at `path`; none if it can't be read or parsed
+fn read_tasks(runner: TaskRunner, path: &Path) -> Vec<TaskDef> {
+    std::fs::read_to_string(path)
+        .map(|text| runner.parse(&text))
+        .unwrap_or_default()
+}
+
+fn task(name: &str, description: Option<&str>) -> TaskDef {
+    TaskDef {
+        name: name.to_owned(),
+        description: description
+            .map(str::trim)
+            .filter(|description| !description.is_empty())
+            .map(str::to_owned),
+        args: Vec::new(),
+    }
+}
+
+/// `[tasks.<name>]` tables, except those marked `private = true`
+fn cargo_make_tasks(text: &str) -> Vec<TaskDef> {
+    let Ok(table) = text.parse::<toml::Table>() else {
+        return Vec::new();
+    };
+    let Some(tasks) = table.get("tasks").and_then(toml::Value::as_table) else {
+        return Vec::new();
+    };
+    tasks
+        .iter()
+        .filter(|(_, task)| task.get("private").and_then(toml::Value::as_bool) != Some(true))
+        .map(|(name, definition)| {
+            let description = definition.get("description").and_then(toml::Value::as_str);
+            task(name, description)
+        })
+        .collect()
+}
+
+/// Explicit targets, skipping special (`.PHONY`), pattern and variable-named targets, and targets
+/// naming files, such as `build/app.o`, which are rarely meant to be made by hand. A
+/// `## comment` after the prerequisites becomes the description, as in self-documenting
+/// Makefiles.
+fn make_targets(text: &str) -> Vec<TaskDef> {
+    let mut targets: Vec<TaskDef> = Vec::new();
+    let mut in_define = false;
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if in_define {
+            in_define = trimmed != "endef";
+            continue;
+        }
+        if trimmed.starts_with("define ") || trimmed == "define" {
+            in_define = true;
+            continue;
+        }
+        // Recipe lines, comments and special targets
+        if line.starts_with(['\t', ' ', '#', '.']) {
+            continue;
+        }
+        let Some((names, rest)) = line.split_once(':') else {
+            continue;
+        };
+        let (prerequisites, description) = match rest.split_once("##") {
+            Some((prerequisites, description)) => (prerequisites, Some(description)),
+            None => (rest, None),
+        };
+        // `:=`, `::=` and target-specific variables are assignments, not rules
+        if names.contains(['=', '$', '%']) || prerequisites.contains('=') {
+            continue;
+        }
+        for name in names.split_whitespace() {
+            let names_file = name.contains('/') || Path::new(name).extension().is_some();
+            if !names_file && !targets.iter().any(|target| target.name == name) {
+                targets.push(task(name, description));
+            }
+        }
+    }
+    targets
+}
+
+/// Public recipes with their parameters; the comment line right above a recipe becomes its
+/// description. Recipes starting with `_` or marked `[private]` are left out.
+fn just_recipes(text: &str) -> Vec<TaskDef> {
+    const KEYWORDS: [&str; 7] = ["alias", "export", "import", "mod", "set", "unexport", "if"];
+    let mut recipes = Vec::new();
+    let mut doc: Option<&str> = None;
+    let mut private = false;
+    for line in text.lines() {
+        if line.trim().is_empty() || line.starts_with([' ', '\t']) {
+            doc = None;
+            private = false;
+            continue;
+        }
+        if let Some(comment) = line.strip_prefix('#') {
+            doc = Some(comment);
+            continue;
+        }
+        if let Some(attributes) = line.strip_prefix('[') {
+            private |= attributes.contains("private");
+            continue;
+        }
+        let header = recipe_header(line);
+        let first_word = line.split_whitespace().next().unwrap_or_default();
+        if let Some(header) = header
+            && !KEYWORDS.contains(&first_word)
+            && let Ok(words) = split_words(header)
+            && let Some((name, params)) = words.split_first()
+        {
+            let name = name.trim_start_matches('@');
+            if !private && !name.starts_with('_') {
+                let mut recipe = task(name, doc);
+                recipe.args = params.iter().map(|param| just_param(param)).collect();
+                recipes.push(recipe);
+            }
+        }
+        doc = None;
+        private = false;
+    }
+    recipes
+}
+
+/// The part of a recipe line before the colon that ends its name and parameters, or `None` if
+/// the line is an assignment or not a recipe at all
+fn recipe_header(line: &str) -> Option<&str> {
+    let mut quote = None;
+    for (index, c) in line.char_indices() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"' | '`') => quote = Some(c),
+            (None, ':') => {
+                let is_assignment = line[index + 1..].starts_with('=');
+                return (!is_assignment).then_some(&line[..index]);
+            }
+            (None, '=') if !line[..index].contains(char::is_whitespace) => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// A recipe parameter such as `target`, `mode='debug'`, `$token` or `+files`
+fn just_param(param: &str) -> ScriptArg {
+    let variadic = param.starts_with(['+', '*']);
+    let param = param.trim_start_matches(['+', '*', '$']);
+    let (name, default) = param.split_once('=').unwrap_or((param, ""));
+    ScriptArg {
+        name: name.to_owned(),
+        kind: if variadic {
+            ArgKind::Words
+        } else {
+            ArgKind::String
+        },
+        default: default.to_owned(),
+        help: None,
+    }
+}
+
+/// Entries of `scripts`, described by their command. `pre<name>` and `post<name>` hooks of
+/// another script run with it, so they are left out.
+fn npm_scripts(text: &str) -> Vec<TaskDef> {
+    let Ok(package) = serde_json::from_str::<serde_json::Value>(text) else {
+        return Vec::new();
+    };
+    let Some(scripts) = package
+        .get("scripts")
+        .and_then(|scripts| scripts.as_object())
+    else {
+        return Vec::new();
+    };
+    scripts
+        .iter()
+        .filter(|(name, _)| {
+            let hooked = name
+                .strip_prefix("pre")
+                .or_else(|| name.strip_prefix("post"));
+            !hooked.is_some_and(|hooked| scripts.contains_key(hooked))
+        })
+        .map(|(name, command)| task(name, command.as_str()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(tasks: &[TaskDef]) -> Vec<&str> {
+        tasks.iter().map(|task| task.name.as_str()).collect()
+    }
+
+    fn descriptions(tasks: &[TaskDef]) -> Vec<Option<&str>> {
+        tasks
+            .iter()
+            .map(|task| task.description.as_deref())
+            .collect()
+    }
+
+    #[test]
+    fn make_lists_explicit_targets_in_file_order() {
+        let makefile = "\
+CC := cc
+FLAGS = -O2
+.PHONY: build test
+build: deps ## Compile everything
+\t$(CC) $(FLAGS) main.c
+test:: build
+\t./run-tests
+
+define RECIPE
+inside: define
+endef
+# comment: not a target
+%.o: %.c
+$(OUT): build
+build/main.o: main.c
+main.o: main.c
+release: FLAGS = -O3
+install uninstall: build ##  Copy the binary  
+";
+        let targets = make_targets(makefile);
+        assert_eq!(names(&targets), ["build", "test", "install", "uninstall"]);
+        assert_eq!(
+            descriptions(&targets),
+            [
+                Some("Compile everything"),
+                None,
+                Some("Copy the binary"),
+                Some("Copy the binary")
+            ]
+        );
+    }
+
+    #[test]
+    fn just_lists_public_recipes_with_parameters() {
+        let justfile = "\
+set shell := [\"bash\", \"-c\"]
+version := \"1.0\"
+alias b := build
+export TOKEN := 'secret'
+
+# Build the project
+build mode='debug' +targets:
+    cargo build
+
+@test $filter:
+    cargo test {{filter}}
+
+[private]
+hidden:
+    true
+
+_helper:
+    true
+
+# Not right above a recipe
+
+deploy target=\"a:b\": build
+    echo {{target}}
+";
+        let recipes = just_recipes(justfile);
+        assert_eq!(names(&recipes), ["build", "test", "deploy"]);
+        assert_eq!(
+            descriptions(&recipes),
+            [Some("Build the project"), None, None]
+        );
+
+        let build = &recipes[0].args;
+        assert_eq!(build.len(), 2);
+        assert_eq!(
+            (build[0].name.as_str(), build[0].default.as_str()),
+            ("mode", "debug")
+        );
+        assert_eq!(build[0].kind, ArgKind::String);
+        assert_eq!(build[1].name, "targets");
+        assert_eq!(build[1].kind, ArgKind::Words);
+        assert_eq!(recipes[1].args[0].name, "filter");
+        assert_eq!(recipes[2].args[0].default, "a:b");
+    }
+
+    #[test]
+    fn recipe_header_stops_at_the_first_unquoted_colon() {
+        assert_eq!(recipe_header("build: deps"), Some("build"));
+        assert_eq!(recipe_header("run arg=':': build"), Some("run arg=':'"));
+        assert_eq!(
+            recipe_header("echo `date +%H:%M`:"),
+            Some("echo `date +%H:%M`")
+        );
+        assert_eq!(recipe_header("version := \"1.0\""), None);
+        assert_eq!(recipe_header("name = 'value:x'"), None);
+        assert_eq!(recipe_header("no colon here"), None);
+    }
+
+    #[test]
+    fn npm_lists_scripts_in_file_order_without_hooks() {
+        let package = r#"{
+            "name": "demo",
+            "scripts": {
+                "test": "jest",
+                "pretest": "lint",
+                "build": "tsc",
+                "postbuild": "copy-assets",
+                "preview": "vite preview"
+            }
+        }"#;
+        let scripts = npm_scripts(package);
+        assert_eq!(names(&scripts), ["test", "build", "preview"]);
+        assert_eq!(
+            descriptions(&scripts),
+            [Some("jest"), Some("tsc"), Some("vite preview")]
+        );
+        assert!(npm_scripts("{ not json").is_empty());
+        assert!(npm_scripts(r#"{"name": "no scripts"}"#).is_empty());
+    }
+
+    #[test]
+    fn cargo_make_lists_public_tasks_in_file_order() {
+        let makefile_toml = r#"
+[config]
+skip_core_tasks = true
+
+[tasks.lint]
+description = "Run clippy"
+command = "cargo"
+
+[tasks.build]
+command = "cargo"
+
+[tasks.helper]
+private = true
+
+[tasks.all]
+dependencies = ["lint", "build"]
+"#;
+        let tasks = cargo_make_tasks(makefile_toml);
+        assert_eq!(names(&tasks), ["lint", "build", "all"]);
+        assert_eq!(descriptions(&tasks), [Some("Run clippy"), None, None]);
+        assert!(cargo_make_tasks("[tasks").is_empty());
+    }
+
+    #[test]
+    fn only_the_file_make_reads_first_is_listed() {
+        let dir =
+            std::env::temp_dir().join(format!("quick_launch-makefiles-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("GNUmakefile"), "gnu:\n").unwrap();
+        std::fs::write(dir.join("Makefile"), "plain:\n").unwrap();
+
+        let gnu = TaskRunner::Make.read_file(&dir.join("GNUmakefile"));
+        let plain = TaskRunner::Make.read_file(&dir.join("Makefile"));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let gnu = gnu.expect("GNUmakefile is a make file");
+        assert_eq!(
+            gnu.iter()
+                .map(|entry| entry.name.as_str())
+                .collect::<Vec<_>>(),
+            ["gnu"]
+        );
+        assert!(plain.expect("Makefile is claimed").is_empty());
+    }
+}
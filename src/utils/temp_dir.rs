@@ -0,0 +1,20 @@
+use std::path::PathBuf;
+
+/// A fresh directory under the system temp dir for one test, removed again when dropped
+pub struct TempDir(pub PathBuf);
+
+impl TempDir {
+    /// `name` keeps the directories of tests running side by side apart
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("quick_launch-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,254 @@
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::path::Path;
+
+/// Placeholder for the command as separate arguments; must be an argument of its own
+pub const CMD: &str = "{cmd}";
+/// Placeholder for the command as one shell-quoted string, for emulators that take it that way
+pub const CMDLINE: &str = "{cmdline}";
+/// Placeholder for the script's folder
+pub const CWD: &str = "{cwd}";
+/// Placeholder for the window title
+pub const TITLE: &str = "{title}";
+
+/// How to start one terminal emulator with a command in it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TerminalProfile {
+    pub name: String,
+    /// Program and arguments, with `{cmd}`, `{cmdline}`, `{cwd}` and `{title}` filled in at launch
+    pub argv: Vec<String>,
+}
+
+impl TerminalProfile {
+    fn new(name: &str, argv: &[&str]) -> Self {
+        TerminalProfile {
+            name: name.to_owned(),
+            argv: argv.iter().map(|arg| (*arg).to_owned()).collect(),
+        }
+    }
+
+    /// The profile's program, used to detect whether the emulator is installed
+    pub fn program(&self) -> Option<&str> {
+        self.argv.first().map(String::as_str)
+    }
+
+    /// Fill in the placeholders; `cmd` is the command to run and `cmdline` the same command
+    /// quoted as a single string
+    pub fn expand(
+        &self,
+        cmd: &[OsString],
+        cmdline: &OsStr,
+        cwd: &Path,
+        title: &str,
+    ) -> Vec<OsString> {
+        let values = [
+            (CMDLINE, cmdline),
+            (CWD, cwd.as_os_str()),
+            (TITLE, OsStr::new(title)),
+        ];
+        let mut argv = Vec::new();
+        for arg in &self.argv {
+            if arg == CMD {
+                argv.extend(cmd.iter().cloned());
+            } else {
+                argv.push(fill(arg, &values));
+            }
+        }
+        argv
+    }
+}
+
+/// Replace every placeholder in `template` with its value
+fn fill(template: &str, values: &[(&str, &OsStr)]) -> OsString {
+    let mut filled = OsString::new();
+    let mut rest = template;
+    while let Some((index, placeholder, value)) = values
+        .iter()
+        .filter_map(|(placeholder, value)| {
+            rest.find(placeholder)
+                .map(|index| (index, *placeholder, *value))
+        })
+        .min_by_key(|(index, ..)| *index)
+    {
+        filled.push(&rest[..index]);
+        filled.push(value);
+        rest = &rest[index + placeholder.len()..];
+    }
+    filled.push(rest);
+    filled
+}
+
+/// Templates for common emulators, in the order auto-detection tries them
+pub fn builtin_profiles() -> Vec<TerminalProfile> {
+    vec![
+        TerminalProfile::new(
+            "gnome-terminal",
+            &[
+                "gnome-terminal",
+                "--title",
+                TITLE,
+                "--working-directory",
+                CWD,
+                "--",
+                CMD,
+            ],
+        ),
+        TerminalProfile::new(
+            "konsole",
+            &[
+                "konsole",
+                "--workdir",
+                CWD,
+                "-p",
+                "tabtitle={title}",
+                "-e",
+                CMD,
+            ],
+        ),
+        TerminalProfile::new(
+            "kitty",
+            &["kitty", "--title", TITLE, "--directory", CWD, CMD],
+        ),
+        TerminalProfile::new("wezterm", &["wezterm", "start", "--cwd", CWD, "--", CMD]),
+        TerminalProfile::new(
+            "alacritty",
+            &[
+                "alacritty",
+                "--title",
+                TITLE,
+                "--working-directory",
+                CWD,
+                "-e",
+                CMD,
+            ],
+        ),
+        TerminalProfile::new(
+            "foot",
+            &["foot", "--title", TITLE, "--working-directory", CWD, CMD],
+        ),
+        TerminalProfile::new(
+            "xfce4-terminal",
+            &[
+                "xfce4-terminal",
+                "--title",
+                TITLE,
+                "--working-directory",
+                CWD,
+                "-x",
+                CMD,
+            ],
+        ),
+        TerminalProfile::new(
+            "tilix",
+            &[
+                "tilix",
+                "--title",
+                TITLE,
+                "--working-directory",
+                CWD,
+                "-e",
+                CMDLINE,
+            ],
+        ),
+        TerminalProfile::new("xterm", &["xterm", "-T", TITLE, "-e", CMD]),
+        TerminalProfile::new("x-terminal-emulator", &["x-terminal-emulator", "-e", CMD]),
+    ]
+}
+
+/// Pick the profile to launch with.
+///
+/// `name` (a script's override, else the configured profile) is looked up among the user's
+/// profiles and then the built-in ones. Without a name, `$TERMINAL` is used (with the matching
+/// built-in template if there is one), then the first built-in emulator found on `PATH`.
+pub fn select_profile(user_profiles: &[TerminalProfile], name: Option<&str>) -> TerminalProfile {
+    let builtins = builtin_profiles();
+    let find = |name: &str| {
+        user_profiles
+            .iter()
+            .chain(&builtins)
+            .find(|profile| profile.name == name)
+            .cloned()
+    };
+    if let Some(profile) = name.and_then(find) {
+        return profile;
+    }
+
+    if let Ok(terminal) = env::var("TERMINAL")
+        && !terminal.is_empty()
+    {
+        let program = Path::new(&terminal)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut profile =
+            find(&program).unwrap_or_else(|| TerminalProfile::new(&program, &["", "-e", CMD]));
+        if let Some(first) = profile.argv.first_mut() {
+            *first = terminal;
+        }
+        return profile;
+    }
+
+    builtins
+        .iter()
+        .find(|profile| profile.program().is_some_and(on_path))
+        .cloned()
+        .unwrap_or_else(|| {
+            TerminalProfile::new("x-terminal-emulator", &["x-terminal-emulator", "-e", CMD])
+        })
+}
+
+/// Whether `program` is an existing file, or an executable file in one of the `PATH` directories
+pub fn is_installed(program: &str) -> bool {
+    let path = Path::new(program);
+    if path.components().count() > 1 {
+        return path.is_file();
+    }
+    on_path(program)
+}
+
+/// Whether `program` is an executable file in one of the `PATH` directories
+fn on_path(program: &str) -> bool {
+    env::var_os("PATH")
+        .is_some_and(|paths| env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+}
+
+/// What the terminal does once the script exits
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeepOpen {
+    /// Close straight away
+    Close,
+    /// Close if the script succeeded, otherwise wait for a key so the error can be read
+    PauseOnFailure,
+    /// Always wait for a key
+    Pause,
+    /// Drop into an interactive `$SHELL`
+    #[default]
+    Shell,
+}
+
+impl KeepOpen {
+    pub const ALL: [KeepOpen; 4] = [
+        KeepOpen::Close,
+        KeepOpen::PauseOnFailure,
+        KeepOpen::Pause,
+        KeepOpen::Shell,
+    ];
+
+    /// Name used in preferences and script headers
+    pub fn name(self) -> &'static str {
+        match self {
+            KeepOpen::Close => "close",
+            KeepOpen::PauseOnFailure => "pause-on-failure",
+            KeepOpen::Pause => "pause",
+            KeepOpen::Shell => "shell",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        KeepOpen::ALL
+            .into_iter()
+            .find(|policy| policy.name() == name.trim().to_ascii_lowercase())
+    }
+}
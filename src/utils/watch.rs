@@ -0,0 +1,47 @@
+use notify_debouncer_mini::notify::{self, RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{DebounceEventResult, Debouncer, new_debouncer};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+
+/// Bursts of changes (a `git pull`, an editor's save dance) are collapsed into one rebuild
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Watches the script roots and their subfolders for added, removed or changed files
+pub struct DirWatcher {
+    _debouncer: Debouncer<RecommendedWatcher>,
+    rx: Receiver<Vec<PathBuf>>,
+}
+
+impl DirWatcher {
+    /// Start watching `dirs`, calling `wake` from the watcher thread whenever changes arrive.
+    ///
+    /// Directories that can't be watched (e.g. because they don't exist) are skipped.
+    pub fn new<'a>(
+        dirs: impl IntoIterator<Item = &'a Path>,
+        wake: impl Fn() + Send + 'static,
+    ) -> notify::Result<Self> {
+        let (tx, rx) = mpsc::channel();
+        let mut debouncer = new_debouncer(DEBOUNCE, move |result: DebounceEventResult| {
+            if let Ok(events) = result {
+                let paths = events.into_iter().map(|event| event.path).collect();
+                // ignore send error (UI might have dropped the watcher)
+                let _ = tx.send(paths);
+                wake();
+            }
+        })?;
+        for dir in dirs {
+            let _ = debouncer.watcher().watch(dir, RecursiveMode::Recursive);
+        }
+        Ok(Self {
+            _debouncer: debouncer,
+            rx,
+        })
+    }
+
+    /// Non-blocking; every path changed since the last call, or `None` if nothing happened.
+    pub fn try_changes(&self) -> Option<Vec<PathBuf>> {
+        let changed: Vec<PathBuf> = self.rx.try_iter().flatten().collect();
+        (!changed.is_empty()).then_some(changed)
+    }
+}